@@ -0,0 +1,29 @@
+#!/bin/bash
+# Compara el numero de instrucciones TM generadas sin y con asignacion de
+# registros para los programas de ejemplo. El resultado queda tambien en
+# bench_output.txt.
+cd "$(dirname "$0")" || exit 1
+(cd lexic-analyzer && cargo build -q 2>/dev/null) || { echo "cargo build failed" >&2; exit 1; }
+BIN=lexic-analyzer/target/debug/lexic-analyzer
+
+# instrucciones TM del programa, o solo las que cumplen el patron $2
+count() {
+    "$BIN" $1 "$2" 2>/dev/null | sed -n '/^; TINY Compilation to TM Code/,$p' \
+        | grep -a -c -E "^[0-9]+: ${3:-}"
+}
+
+{
+    printf "%-34s %10s %10s %10s %10s\n" "programa" "total-sin" "total-con" "LD/ST-sin" "LD/ST-con"
+    total_before=0
+    total_after=0
+    for file in ide_unix/*.tny lexic-analyzer/*.tny; do
+        before=$(count --disable-pass=regalloc "$file")
+        after=$(count "" "$file")
+        ldst_before=$(count --disable-pass=regalloc "$file" "(LD|ST) ")
+        ldst_after=$(count "" "$file" "(LD|ST) ")
+        printf "%-34s %10d %10d %10d %10d\n" "$file" "$before" "$after" "$ldst_before" "$ldst_after"
+        total_before=$((total_before + before))
+        total_after=$((total_after + after))
+    done
+    printf "%-34s %10d %10d\n" "total" "$total_before" "$total_after"
+} | tee bench_output.txt
@@ -65,11 +65,107 @@ mod compiler {
         NoType,
     }
 
+    impl TinyType {
+        pub fn name(&self) -> &'static str {
+            match self {
+                TinyType::Integer => "int",
+                TinyType::Float => "float",
+                TinyType::Boolean => "bool",
+                TinyType::NoType => "no_type",
+            }
+        }
+    }
+
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    pub enum ReferenceKind {
+        Declared,
+        Read,
+        Written,
+    }
+
+    impl ReferenceKind {
+        pub fn name(&self) -> &'static str {
+            match self {
+                ReferenceKind::Declared => "declared",
+                ReferenceKind::Read => "read",
+                ReferenceKind::Written => "written",
+            }
+        }
+    }
+
+    // Cada aparicion de una variable en el codigo: la columna final es exclusiva
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    pub struct Reference {
+        pub kind: ReferenceKind,
+        pub line: u32,
+        pub column: u32,
+        pub end_column: u32,
+    }
+
+    impl Reference {
+        pub fn new(token: &Token, kind: ReferenceKind) -> Reference {
+            Reference {
+                kind,
+                line: token.line,
+                column: token.column,
+                end_column: token.column + token.lexema.chars().count() as u32,
+            }
+        }
+    }
+
     #[derive(Clone, PartialEq, Eq)]
     pub struct BucketList {
-        lines: Vec<u32>,
+        name: String,
+        references: Vec<Reference>,
         mem_location: u32,
         data_type: TinyType,
+        // Profundidad del bloque donde se declaro, 0 son las variables del programa
+        depth: u32,
+        // Simbolo de un bloque exterior con el mismo nombre que esta declaracion oculta
+        shadows: Option<usize>,
+    }
+
+    impl BucketList {
+        pub fn name(&self) -> &str {
+            &self.name
+        }
+
+        pub fn references(&self) -> &Vec<Reference> {
+            &self.references
+        }
+
+        // Donde aparece la declaracion; la variable es visible desde ahi hasta el fin del bloque
+        pub fn declaration(&self) -> &Reference {
+            &self.references[0]
+        }
+
+        // Lineas donde aparece la variable, ordenadas y sin repetir
+        pub fn lines(&self) -> Vec<u32> {
+            let mut lines: Vec<u32> = self.references.iter().map(|r| r.line).collect();
+            lines.sort_unstable();
+            lines.dedup();
+            lines
+        }
+
+        pub fn count(&self, kind: ReferenceKind) -> usize {
+            self.references.iter().filter(|r| r.kind == kind).count()
+        }
+
+        pub fn mem_location(&self) -> u32 {
+            self.mem_location
+        }
+
+        pub fn data_type(&self) -> TinyType {
+            self.data_type
+        }
+
+        pub fn depth(&self) -> u32 {
+            self.depth
+        }
+
+        pub fn shadows(&self) -> Option<usize> {
+            self.shadows
+        }
     }
 
     #[derive(Copy, Clone, PartialEq, Eq)]
@@ -96,6 +192,7 @@ mod compiler {
         BooleanExp,
         VariableSeq,
         ListVariableDec,
+        Cast,
     }
 
     #[derive(Debug, Clone)]
@@ -103,6 +200,7 @@ mod compiler {
         pub token: TokenType,
         pub lexema: String,
         pub line: u32,
+        pub column: u32,
     }
 
     #[derive(Clone)]
@@ -113,6 +211,8 @@ mod compiler {
         statement_type: StatementType,
         val_type: TinyType,
         is_lvalue: bool,
+        // Indice en la tabla de simbolos de la variable, lo llena build_table
+        symbol: Option<usize>,
     }
 
     pub fn null_tree() -> TreeNode {
@@ -121,12 +221,14 @@ mod compiler {
                 token: TokenType::NoToken,
                 lexema: String::from(""),
                 line: 0,
+                column: 0,
             },
             is_expression: false,
             nodes: vec![],
             statement_type: StatementType::NoType,
             val_type: TinyType::NoType,
             is_lvalue: false,
+            symbol: None,
         };
     }
 
@@ -138,6 +240,7 @@ mod compiler {
             nodes: vec![],
             statement_type: StatementType::Literal,
             val_type: data_type,
+            symbol: None,
         };
         return result;
     }
@@ -149,6 +252,7 @@ mod compiler {
             nodes: vec![],
             statement_type: StatementType::Variable,
             val_type: TinyType::NoType,
+            symbol: None,
         };
         return result;
     }
@@ -164,6 +268,7 @@ mod compiler {
             statement_type: StatementType::Arithmetic,
             val_type: TinyType::NoType,
             is_lvalue: false,
+            symbol: None,
         };
         return result;
     }
@@ -180,6 +285,7 @@ mod compiler {
             statement_type: StatementType::Relational,
             val_type: TinyType::NoType,
             is_lvalue: false,
+            symbol: None,
         };
         return result;
     }
@@ -192,6 +298,7 @@ mod compiler {
             statement_type: StatementType::Assignment,
             val_type: TinyType::NoType,
             is_lvalue: false,
+            symbol: None,
         };
         return result;
     }
@@ -204,6 +311,22 @@ mod compiler {
             nodes: vec![b_factor.copy()],
             statement_type: StatementType::Not,
             val_type: TinyType::Boolean,
+            symbol: None,
+        };
+        return result;
+    }
+
+    // Conversion entre int y float. Las conversiones implicitas que agrega el
+    // checker usan un token NoToken para que no aparezcan en los arboles impresos.
+    pub fn new_cast(token: &Token, exp: &TreeNode, val_type: TinyType) -> TreeNode {
+        let result = TreeNode {
+            token: token.copy_token(),
+            is_expression: true,
+            is_lvalue: false,
+            nodes: vec![exp.copy()],
+            statement_type: StatementType::Cast,
+            val_type,
+            symbol: None,
         };
         return result;
     }
@@ -221,6 +344,7 @@ mod compiler {
             nodes: vec![condition.copy(), selection.copy(), otherwise.copy()],
             statement_type: StatementType::If,
             val_type: TinyType::NoType,
+            symbol: None,
         };
         return result;
     }
@@ -233,6 +357,7 @@ mod compiler {
             nodes: vec![condition.copy(), selection.copy()],
             statement_type: StatementType::Repeat,
             val_type: TinyType::NoType,
+            symbol: None,
         };
         return result;
     }
@@ -245,6 +370,7 @@ mod compiler {
             nodes: vec![condition.copy(), selection.copy()],
             statement_type: StatementType::While,
             val_type: TinyType::NoType,
+            symbol: None,
         };
         return result;
     }
@@ -257,6 +383,7 @@ mod compiler {
             nodes: vec![variables.copy(), program.copy()],
             statement_type: StatementType::Program,
             val_type: TinyType::NoType,
+            symbol: None,
         };
         return result;
     }
@@ -267,12 +394,14 @@ mod compiler {
                 token: TokenType::NoToken,
                 lexema: String::from(""),
                 line: 0,
+                column: 0,
             },
             is_expression: false,
             is_lvalue: false,
             nodes: vec![stmt.copy()],
             statement_type: StatementType::Sequence,
             val_type: TinyType::NoType,
+            symbol: None,
         };
         return result;
     }
@@ -285,6 +414,7 @@ mod compiler {
             nodes: vec![variable.copy()],
             statement_type: StatementType::Read,
             val_type: TinyType::NoType,
+            symbol: None,
         };
         return result;
     }
@@ -297,6 +427,7 @@ mod compiler {
             nodes: vec![exp.copy()],
             statement_type: StatementType::Write,
             val_type: TinyType::NoType,
+            symbol: None,
         };
         return result;
     }
@@ -309,6 +440,7 @@ mod compiler {
             nodes: vec![],
             statement_type: StatementType::LiteralBoolExp,
             val_type: TinyType::Boolean,
+            symbol: None,
         };
         return result;
     }
@@ -321,6 +453,7 @@ mod compiler {
             nodes: vec![left.copy(), right.copy()],
             statement_type: StatementType::BooleanExp,
             val_type: TinyType::Boolean,
+            symbol: None,
         };
         return result;
     }
@@ -331,12 +464,14 @@ mod compiler {
                 token: TokenType::NoToken,
                 lexema: String::from(""),
                 line: 0,
+                column: 0,
             },
             is_expression: false,
             is_lvalue: false,
             nodes: vec![variables.copy()],
             statement_type: StatementType::VariableSeq,
             val_type: TinyType::NoType,
+            symbol: None,
         };
         return result;
     }
@@ -348,6 +483,7 @@ mod compiler {
             nodes: vec![variables.copy()],
             statement_type: StatementType::ListVariableDec,
             val_type: val_type,
+            symbol: None,
         };
         return result;
     }
@@ -361,29 +497,27 @@ mod compiler {
                 statement_type: self.statement_type,
                 val_type: self.val_type,
                 is_lvalue: self.is_lvalue,
+                symbol: self.symbol,
             };
         }
         pub fn type_name(&self) -> &str {
-            match &self.val_type {
-                TinyType::Integer => {
-                    return "int";
-                }
-                TinyType::Float => {
-                    return "float";
-                }
-                TinyType::Boolean => {
-                    return "bool";
-                }
-                _ => {
-                    return "no_type";
-                }
-            }
+            self.val_type.name()
         }
 
         pub fn is_expression(&self) -> bool {
             self.is_expression
         }
 
+        // Valor de un literal numerico. El checker reporta los enteros que no
+        // caben en 32 bits y los deja en 0, asi despues siempre se pueden leer.
+        pub fn int_value(&self) -> i32 {
+            self.token.lexema.parse().expect("integer literal checked by typeChecking")
+        }
+
+        pub fn float_value(&self) -> f64 {
+            self.token.lexema.parse().expect("float literal from the scanner")
+        }
+
         pub fn get_type(&self) -> TinyType {
             return self.val_type;
         }
@@ -420,19 +554,26 @@ mod compiler {
         }
 
         pub fn print_syntax_tree(&self, number_idents: u32) {
+            print!("{}", self.syntax_tree_text(number_idents));
+        }
+
+        pub fn syntax_tree_text(&self, number_idents: u32) -> String {
+            let mut out = String::new();
             if self.statement_type != StatementType::Sequence
                 && self.statement_type != StatementType::VariableSeq
                 && self.token.token != TokenType::NoToken
             {
-                for n in 1..number_idents {
-                    print!(" ");
+                for _ in 1..number_idents {
+                    out.push(' ');
                 }
-                println!("{}", self.token.lexema);
+                out.push_str(&self.token.lexema);
+                out.push('\n');
             }
 
             for node in &self.nodes {
-                node.print_syntax_tree(number_idents + 1);
+                out.push_str(&node.syntax_tree_text(number_idents + 1));
             }
+            out
         }
     }
     pub fn null_token() -> Token {
@@ -440,6 +581,7 @@ mod compiler {
             token: TokenType::NoToken,
             lexema: String::from(""),
             line: 0,
+            column: 0,
         };
         return result;
     }
@@ -449,11 +591,594 @@ mod compiler {
                 token: self.token,
                 lexema: self.lexema.clone(),
                 line: self.line,
+                column: self.column,
             };
             return new_token;
         }
     }
 
+    pub mod diagnostics {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // Errores reportados, para el codigo de salida del compilador
+        static ERROR_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+        pub enum Severity {
+            Error,
+            Warning,
+        }
+
+        // Codigos estables para cada error del compilador. El texto de los mensajes
+        // puede cambiar entre versiones, el codigo no.
+        #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+        pub enum ErrorCode {
+            UndeclaredVariable,
+            DoubleDeclaration,
+            NonBooleanIfCondition,
+            NonBooleanWhileCondition,
+            NonBooleanUntilCondition,
+            NonBooleanNotOperand,
+            NonBooleanLogicalOperand,
+            InvalidArithmeticOperand,
+            InvalidComparisonOperand,
+            AssignmentTypeMismatch,
+            UnexpectedToken,
+            InvalidDeclaration,
+            ExpectedStatement,
+            ExpectedExpression,
+            CodeAfterProgramEnd,
+            UseBeforeDeclaration,
+            FloatToIntWithoutCast,
+            InvalidCastOperand,
+            ConstantArithmeticError,
+            IntegerLiteralOutOfRange,
+        }
+
+        pub const ALL_CODES: [ErrorCode; 20] = [
+            ErrorCode::UndeclaredVariable,
+            ErrorCode::DoubleDeclaration,
+            ErrorCode::NonBooleanIfCondition,
+            ErrorCode::NonBooleanWhileCondition,
+            ErrorCode::NonBooleanUntilCondition,
+            ErrorCode::NonBooleanNotOperand,
+            ErrorCode::NonBooleanLogicalOperand,
+            ErrorCode::InvalidArithmeticOperand,
+            ErrorCode::InvalidComparisonOperand,
+            ErrorCode::AssignmentTypeMismatch,
+            ErrorCode::UnexpectedToken,
+            ErrorCode::InvalidDeclaration,
+            ErrorCode::ExpectedStatement,
+            ErrorCode::ExpectedExpression,
+            ErrorCode::CodeAfterProgramEnd,
+            ErrorCode::UseBeforeDeclaration,
+            ErrorCode::FloatToIntWithoutCast,
+            ErrorCode::InvalidCastOperand,
+            ErrorCode::ConstantArithmeticError,
+            ErrorCode::IntegerLiteralOutOfRange,
+        ];
+
+        impl ErrorCode {
+            pub fn code(&self) -> &'static str {
+                match self {
+                    ErrorCode::UndeclaredVariable => "E0001",
+                    ErrorCode::DoubleDeclaration => "E0002",
+                    ErrorCode::NonBooleanIfCondition => "E0003",
+                    ErrorCode::NonBooleanWhileCondition => "E0004",
+                    ErrorCode::NonBooleanUntilCondition => "E0005",
+                    ErrorCode::NonBooleanNotOperand => "E0006",
+                    ErrorCode::NonBooleanLogicalOperand => "E0007",
+                    ErrorCode::InvalidArithmeticOperand => "E0008",
+                    ErrorCode::InvalidComparisonOperand => "E0009",
+                    ErrorCode::AssignmentTypeMismatch => "E0010",
+                    ErrorCode::UnexpectedToken => "E0011",
+                    ErrorCode::InvalidDeclaration => "E0012",
+                    ErrorCode::ExpectedStatement => "E0013",
+                    ErrorCode::ExpectedExpression => "E0014",
+                    ErrorCode::CodeAfterProgramEnd => "E0015",
+                    ErrorCode::UseBeforeDeclaration => "E0016",
+                    ErrorCode::FloatToIntWithoutCast => "E0017",
+                    ErrorCode::InvalidCastOperand => "E0018",
+                    ErrorCode::ConstantArithmeticError => "E0019",
+                    ErrorCode::IntegerLiteralOutOfRange => "E0020",
+                }
+            }
+
+            pub fn from_code(code: &str) -> Option<ErrorCode> {
+                let code = code.trim().to_uppercase();
+                ALL_CODES.iter().copied().find(|c| c.code() == code)
+            }
+
+            pub fn title(&self) -> &'static str {
+                match self {
+                    ErrorCode::UndeclaredVariable => "use of an undeclared variable",
+                    ErrorCode::DoubleDeclaration => "variable declared more than once",
+                    ErrorCode::NonBooleanIfCondition => "`if` condition is not boolean",
+                    ErrorCode::NonBooleanWhileCondition => "`while` condition is not boolean",
+                    ErrorCode::NonBooleanUntilCondition => "`do ... until` condition is not boolean",
+                    ErrorCode::NonBooleanNotOperand => "operand of `not` is not boolean",
+                    ErrorCode::NonBooleanLogicalOperand => "operand of `and`/`or` is not boolean",
+                    ErrorCode::InvalidArithmeticOperand => "arithmetic on a non-numeric value",
+                    ErrorCode::InvalidComparisonOperand => "comparison of non-numeric values",
+                    ErrorCode::AssignmentTypeMismatch => "type mismatch in assignment",
+                    ErrorCode::UnexpectedToken => "unexpected token",
+                    ErrorCode::InvalidDeclaration => "invalid variable declaration",
+                    ErrorCode::ExpectedStatement => "expected a statement",
+                    ErrorCode::ExpectedExpression => "expected an expression",
+                    ErrorCode::CodeAfterProgramEnd => "code after the end of the program",
+                    ErrorCode::UseBeforeDeclaration => "variable used before its declaration",
+                    ErrorCode::FloatToIntWithoutCast => "float value stored in an int without a cast",
+                    ErrorCode::InvalidCastOperand => "`int(...)`/`float(...)` applied to a non-numeric value",
+                    ErrorCode::ConstantArithmeticError => "constant arithmetic that always fails",
+                    ErrorCode::IntegerLiteralOutOfRange => "integer literal does not fit in an `int`",
+                }
+            }
+
+            pub fn explanation(&self) -> &'static str {
+                match self {
+                    ErrorCode::UndeclaredVariable => {
+                        r#"A variable was used but never declared. Every variable must be
+declared with its type (`int`, `float` or `bool`) before it is read or
+assigned.
+
+Erroneous example:
+
+    program {
+        int x;
+        y = 5;
+    }
+
+Corrected example:
+
+    program {
+        int x, y;
+        y = 5;
+    }
+"#
+                    }
+                    ErrorCode::DoubleDeclaration => {
+                        r#"The same name was declared twice in the same block. Inside one block a
+name can only have one type and one memory location. An inner block may
+declare a name that already exists outside it; that only shadows the outer
+variable (see the `shadowed_variables` lint).
+
+Erroneous example:
+
+    program {
+        int x;
+        float x;
+    }
+
+Corrected example:
+
+    program {
+        int x;
+        float y;
+    }
+"#
+                    }
+                    ErrorCode::NonBooleanIfCondition => {
+                        r#"The condition of an `if` statement must be a `bool` value, such as a
+comparison or a boolean variable. Numbers are not implicitly converted to
+booleans.
+
+Erroneous example:
+
+    program {
+        int x;
+        x = 1;
+        if (x) then { write x; } fi
+    }
+
+Corrected example:
+
+    program {
+        int x;
+        x = 1;
+        if (x != 0) then { write x; } fi
+    }
+"#
+                    }
+                    ErrorCode::NonBooleanWhileCondition => {
+                        r#"The condition of a `while` loop must be a `bool` value. Numbers are
+not implicitly converted to booleans.
+
+Erroneous example:
+
+    program {
+        int n;
+        n = 3;
+        while (n) { n = n - 1; }
+    }
+
+Corrected example:
+
+    program {
+        int n;
+        n = 3;
+        while (n > 0) { n = n - 1; }
+    }
+"#
+                    }
+                    ErrorCode::NonBooleanUntilCondition => {
+                        r#"The condition after `until` in a `do ... until` loop must be a `bool`
+value. Numbers are not implicitly converted to booleans.
+
+Erroneous example:
+
+    program {
+        int n;
+        n = 3;
+        do { n = n - 1; } until (n);
+    }
+
+Corrected example:
+
+    program {
+        int n;
+        n = 3;
+        do { n = n - 1; } until (n == 0);
+    }
+"#
+                    }
+                    ErrorCode::NonBooleanNotOperand => {
+                        r#"The operand of `not` must be a `bool` value.
+
+Erroneous example:
+
+    program {
+        bool b;
+        b = not 1;
+    }
+
+Corrected example:
+
+    program {
+        bool b;
+        b = not true;
+    }
+"#
+                    }
+                    ErrorCode::NonBooleanLogicalOperand => {
+                        r#"Both operands of `and` and `or` must be `bool` values.
+
+Erroneous example:
+
+    program {
+        int x;
+        bool b;
+        b = x and true;
+    }
+
+Corrected example:
+
+    program {
+        int x;
+        bool b;
+        b = x > 0 and true;
+    }
+"#
+                    }
+                    ErrorCode::InvalidArithmeticOperand => {
+                        r#"The operators `+`, `-`, `*` and `/` only work on `int` and `float`
+values. One of the operands is a `bool` or has no valid type.
+
+Erroneous example:
+
+    program {
+        int x;
+        bool b;
+        x = b + 1;
+    }
+
+Corrected example:
+
+    program {
+        int x, y;
+        x = y + 1;
+    }
+"#
+                    }
+                    ErrorCode::InvalidComparisonOperand => {
+                        r#"The relational operators `<`, `<=`, `>`, `>=`, `==` and `!=` only
+compare `int` and `float` values. One of the operands is a `bool` or has
+no valid type.
+
+Erroneous example:
+
+    program {
+        bool a, b;
+        if (a < b) then { write 1; } fi
+    }
+
+Corrected example:
+
+    program {
+        int a, b;
+        if (a < b) then { write 1; } fi
+    }
+"#
+                    }
+                    ErrorCode::AssignmentTypeMismatch => {
+                        r#"The value on the right of `=` does not have a type that can be
+stored in the variable on the left. Numeric variables need a numeric
+value and `bool` variables need a boolean value.
+
+Erroneous example:
+
+    program {
+        int x;
+        x = true;
+    }
+
+Corrected example:
+
+    program {
+        bool x;
+        x = true;
+    }
+"#
+                    }
+                    ErrorCode::UnexpectedToken => {
+                        r#"The parser expected a specific token (a keyword, a symbol such as `;`
+or `)`, or an identifier) and found something else. A common cause is a
+missing `;` or a missing `then`/`fi` in an `if` statement.
+
+Erroneous example:
+
+    program {
+        int x;
+        if (x > 0) { x = 1; }
+    }
+
+Corrected example:
+
+    program {
+        int x;
+        if (x > 0) then { x = 1; } fi
+    }
+"#
+                    }
+                    ErrorCode::InvalidDeclaration => {
+                        r#"This error is no longer emitted. Programs used to require a declaration
+list before the first statement; declarations are now allowed anywhere a
+statement is. A program that uses a variable without declaring it reports
+E0001 instead.
+
+Erroneous example:
+
+    program {
+        x = 1;
+    }
+
+Corrected example:
+
+    program {
+        int x;
+        x = 1;
+    }
+"#
+                    }
+                    ErrorCode::ExpectedStatement => {
+                        r#"A statement was expected: an assignment, `if`, `while`, `do`, `read`,
+`write` or a `{ ... }` block. The token found cannot start a statement.
+
+Erroneous example:
+
+    program {
+        int x;
+        5 = x;
+    }
+
+Corrected example:
+
+    program {
+        int x;
+        x = 5;
+    }
+"#
+                    }
+                    ErrorCode::ExpectedExpression => {
+                        r#"An expression was expected: a number, a variable, `true`, `false` or a
+parenthesized expression. The token found cannot start an expression.
+
+Erroneous example:
+
+    program {
+        int x;
+        x = * 2;
+    }
+
+Corrected example:
+
+    program {
+        int x;
+        x = x * 2;
+    }
+"#
+                    }
+                    ErrorCode::UseBeforeDeclaration => {
+                        r#"A variable is visible from its declaration to the end of the block that
+contains it. It was used in that block before the line that declares it.
+
+Erroneous example:
+
+    program {
+        x = 1;
+        int x;
+    }
+
+Corrected example:
+
+    program {
+        int x;
+        x = 1;
+    }
+"#
+                    }
+                    ErrorCode::FloatToIntWithoutCast => {
+                        r#"A `float` value was assigned to an `int` variable, or used to initialize
+one. The fractional part would be lost, so the conversion must be written
+explicitly with `int(...)`, which truncates toward zero. The opposite
+direction, `int` to `float`, happens implicitly.
+
+Erroneous example:
+
+    program {
+        int x;
+        float f = 2.5;
+        x = f * 2;
+    }
+
+Corrected example:
+
+    program {
+        int x;
+        float f = 2.5;
+        x = int(f * 2);
+    }
+"#
+                    }
+                    ErrorCode::InvalidCastOperand => {
+                        r#"The conversions `int(...)` and `float(...)` only convert between the
+numeric types. A `bool` value cannot be converted to a number.
+
+Erroneous example:
+
+    program {
+        bool b = true;
+        int x = int(b);
+    }
+
+Corrected example:
+
+    program {
+        bool b = true;
+        int x = 0;
+        if (b) then { x = 1; } fi
+    }
+"#
+                    }
+                    ErrorCode::ConstantArithmeticError => {
+                        r#"The compiler evaluates constant expressions, and this one fails every
+time it runs: the divisor of a `/` is zero, or an `int` operation on
+constants gives a value that does not fit in 32 bits. `x / (2 - 2)` and
+`65536 * 65536` are both reported.
+
+Erroneous example:
+
+    program {
+        int x = 10;
+        x = x / (3 - 3);
+        x = 2147483647 + 1;
+    }
+
+Corrected example:
+
+    program {
+        int x = 10;
+        x = x / 3;
+        x = 2147483647;
+    }
+"#
+                    }
+                    ErrorCode::IntegerLiteralOutOfRange => {
+                        r#"An `int` is a 32-bit signed integer, so an integer literal can be at
+most 2147483647. Larger values cannot be stored in an `int` variable; use a
+`float` literal instead.
+
+Erroneous example:
+
+    program {
+        int x;
+        x = 3000000000;
+    }
+
+Corrected example:
+
+    program {
+        float x;
+        x = 3000000000.0;
+    }
+"#
+                    }
+                    ErrorCode::CodeAfterProgramEnd => {
+                        r#"The closing `}` of `program { ... }` must be the last token in the
+file. Anything after it is not compiled.
+
+Erroneous example:
+
+    program {
+        int x;
+    }
+    x = 1;
+
+Corrected example:
+
+    program {
+        int x;
+        x = 1;
+    }
+"#
+                    }
+                }
+            }
+        }
+
+        // `id` es el codigo del error (E0xxx) o el nombre del lint que lo genero
+        pub fn report(severity: Severity, id: &str, line: u32, msg: &str) {
+            let label = match severity {
+                Severity::Error => {
+                    ERROR_COUNT.fetch_add(1, Ordering::Relaxed);
+                    "error"
+                }
+                Severity::Warning => "warning",
+            };
+            eprintln!("{}[{}]: line - {} {}: {}", label, id, line, label, msg);
+        }
+
+        pub fn error(code: ErrorCode, line: u32, msg: &str) {
+            report(Severity::Error, code.code(), line, msg);
+        }
+
+        pub fn error_count() -> usize {
+            ERROR_COUNT.load(Ordering::Relaxed)
+        }
+
+        // Texto que imprime `--explain E0xxx`
+        pub fn explain(code: &str) -> Option<String> {
+            ErrorCode::from_code(code).map(|c| {
+                format!("{}: {}\n\n{}", c.code(), c.title(), c.explanation())
+            })
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use super::*;
+
+            #[test]
+            fn every_code_has_an_explanation() {
+                for (index, code) in ALL_CODES.iter().enumerate() {
+                    // los codigos van en orden y sin huecos
+                    assert_eq!(code.code(), format!("E{:04}", index + 1));
+                    assert_eq!(ErrorCode::from_code(code.code()), Some(*code));
+                    assert_eq!(ErrorCode::from_code(&code.code().to_lowercase()), Some(*code));
+                    let text = explain(code.code()).unwrap();
+                    assert!(text.starts_with(&format!("{}: {}\n\n", code.code(), code.title())), "{}", text);
+                    assert!(text.contains("Erroneous example:"), "{}", code.code());
+                    assert!(text.contains("Corrected example:"), "{}", code.code());
+                }
+            }
+
+            #[test]
+            fn unknown_codes_have_no_explanation() {
+                for code in ["E0000", "E9999", "E001", "W0001", ""].iter() {
+                    assert_eq!(explain(code), None, "{}", code);
+                }
+            }
+        }
+    }
+
     pub mod scanner {
         use super::Token;
         use super::TokenType;
@@ -482,6 +1207,10 @@ mod compiler {
             initialized: bool,
             line_buff: Vec<char>,
             in_eof: bool,
+            comment: String,
+            pragmas: Vec<(u32, String)>,
+            // lineas leidas, solo con _echo_source
+            source_lines: Vec<String>,
         }
         impl Scanner {
             pub fn set_echo_source(&mut self, x: bool) {
@@ -514,6 +1243,9 @@ mod compiler {
                         .read_line(&mut buf)
                         .expect("Couldn't read file'");
                     if num_bytes > zero_bytes {
+                        if self._echo_source {
+                            self.source_lines.push(String::from(buf.trim_end_matches(&['\r', '\n'][..])));
+                        }
                         self.line_buff = buf.chars().collect();
                         self.current_pos = 0;
                         self.current_line += 1;
@@ -547,10 +1279,31 @@ mod compiler {
                     initialized: false,
                     line_buff: vec![],
                     in_eof: false,
+                    comment: String::new(),
+                    pragmas: vec![],
+                    source_lines: vec![],
                 };
                 return result;
             }
 
+            // Comentarios de la forma `// lint: allow(unused_variables)` que el
+            // escaner encontro, con su linea
+            pub fn pragmas(&self) -> &Vec<(u32, String)> {
+                &self.pragmas
+            }
+
+            pub fn source_lines(&self) -> &Vec<String> {
+                &self.source_lines
+            }
+
+            fn end_comment(&mut self) {
+                let text = self.comment.trim();
+                if let Some(pragma) = text.strip_prefix("lint:") {
+                    self.pragmas.push((self.current_line, String::from(pragma.trim())));
+                }
+                self.comment.clear();
+            }
+
             fn is_delimiter(&self, c: char) -> bool {
                 c == ' ' || c == '\t' || c == '\n'
             }
@@ -587,6 +1340,7 @@ mod compiler {
                 let mut lexema: String = String::new();
                 let mut token: TokenType = TokenType::TK_ERROR;
                 let mut start_line: u32 = 0;
+                let mut start_column: u32 = 0;
 
                 let mut state: StateType = StateType::Start;
                 while state != StateType::IsDone {
@@ -595,6 +1349,7 @@ mod compiler {
                     match state {
                         StateType::Start => {
                             start_line = self.get_line();
+                            start_column = self.current_pos as u32;
                             if self.in_eof {
                                 state = StateType::IsDone;
                                 token = TokenType::TK_EOF;
@@ -638,11 +1393,13 @@ mod compiler {
                                 if tempC == '/' {
                                     state = StateType::CommentLine;
                                     token = TokenType::TK_COMMENT_LINE;
-                                    save = false
+                                    save = false;
+                                    self.comment.clear();
                                 } else if tempC == '*' {
                                     state = StateType::CommentBlock;
                                     token = TokenType::TK_COMMENT_BLOCK;
                                     save = false;
+                                    self.comment.clear();
                                 } else {
                                     self.unget_next_char();
                                 }
@@ -725,9 +1482,11 @@ mod compiler {
                         }
                         StateType::CommentLine => {
                             save = false;
+                            self.comment.push(c);
                             if self.current_pos >= self.line_buff.len() {
                                 token = TokenType::TK_COMMENT_LINE;
                                 state = StateType::Start;
+                                self.end_comment();
                             }
                         }
                         StateType::CommentBlock => {
@@ -736,7 +1495,9 @@ mod compiler {
                             if (c == '*') && (tempC == '/') {
                                 token = TokenType::TK_COMMENT_BLOCK;
                                 state = StateType::Start;
+                                self.end_comment();
                             } else {
+                                self.comment.push(c);
                                 self.unget_next_char();
                             }
                         }
@@ -757,6 +1518,7 @@ mod compiler {
                     token: token,
                     lexema: String::from(lexema),
                     line: start_line,
+                    column: start_column,
                 };
                 if self._trace {
                     println!(
@@ -769,570 +1531,4499 @@ mod compiler {
         }
     }
 
-    pub mod codegen {
-        use super::TreeNode;
+    // Representacion intermedia de tres direcciones. Los temporales y las
+    // etiquetas se numeran desde 0 y las variables usan el indice de la
+    // tabla de simbolos.
+    pub mod ir {
         use super::analyzer::SymbolTable;
         use super::StatementType;
+        use super::TinyType;
         use super::TokenType;
+        use super::TreeNode;
 
-        pub struct CodeGenResult {
-            emit_loc: i64,
-            high_emit_loc: i64,
-            tmp_offset: i64
+        #[derive(Debug, Copy, Clone, PartialEq)]
+        pub enum Operand {
+            Temp(usize),
+            Var(usize),
+            Int(i32),
+            Float(f64),
+            Bool(bool),
         }
 
-        impl CodeGenResult {
-            pub fn new() -> CodeGenResult {
-                CodeGenResult {
-                    emit_loc: 0,
-                    high_emit_loc: 0,
-                    tmp_offset: 0
+        #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+        pub enum Op {
+            Add,
+            Sub,
+            Mul,
+            Div,
+            Lt,
+            Le,
+            Gt,
+            Ge,
+            Eq,
+            Ne,
+        }
+
+        impl Op {
+            pub fn from_token(token: &TokenType) -> Option<Op> {
+                match token {
+                    TokenType::TK_PLUS => Some(Op::Add),
+                    TokenType::TK_MINUS => Some(Op::Sub),
+                    TokenType::TK_TIMES => Some(Op::Mul),
+                    TokenType::TK_OVER => Some(Op::Div),
+                    TokenType::TK_LT => Some(Op::Lt),
+                    TokenType::TK_LTE => Some(Op::Le),
+                    TokenType::TK_GT => Some(Op::Gt),
+                    TokenType::TK_GTE => Some(Op::Ge),
+                    TokenType::TK_EQ => Some(Op::Eq),
+                    TokenType::TK_DIF => Some(Op::Ne),
+                    _ => None,
                 }
             }
 
-            pub fn code_gen(&mut self, node: &TreeNode, st: &mut SymbolTable){
-                self.emit_comment("TINY Compilation to TM Code");
-                self.emit_comment("Standard prelude:");
-                self.emit_rm("LD", 6, 0, 0, "load maxaddress from location 0");
-                self.emit_rm("ST", 0, 0, 0, "clear location 0");
-                self.emit_comment("End of standard prelude.");
-                self.code_gen_helper(node, st);
-                self.emit_comment("End of execution.");
-                self.emit_ro("HALT", 0, 0, 0, "");
+            pub fn symbol(&self) -> &'static str {
+                match self {
+                    Op::Add => "+",
+                    Op::Sub => "-",
+                    Op::Mul => "*",
+                    Op::Div => "/",
+                    Op::Lt => "<",
+                    Op::Le => "<=",
+                    Op::Gt => ">",
+                    Op::Ge => ">=",
+                    Op::Eq => "==",
+                    Op::Ne => "!=",
+                }
             }
-    
-            fn emit_comment(&mut self, comment: &str){
-                println!("; {}", comment);
+
+            pub fn is_relational(&self) -> bool {
+                !matches!(self, Op::Add | Op::Sub | Op::Mul | Op::Div)
             }
 
-            fn emit_rm(&mut self, op: &str, r: i64, d: i64, s: i64, comment: &str){
-                println!("{}: {} {},{}({})", self.emit_loc, op, r, d, s);
-                self.emit_loc += 1;
-                self.emit_comment(comment);
-                if self.emit_loc > self.high_emit_loc {
-                    self.high_emit_loc = self.emit_loc;
+            // relacion contraria, para saltar cuando la condicion es falsa
+            pub fn negate(&self) -> Op {
+                match self {
+                    Op::Lt => Op::Ge,
+                    Op::Le => Op::Gt,
+                    Op::Gt => Op::Le,
+                    Op::Ge => Op::Lt,
+                    Op::Eq => Op::Ne,
+                    Op::Ne => Op::Eq,
+                    other => *other,
                 }
             }
+        }
+
+        // `ty` es el tipo de los operandos; una relacion guarda un bool en `dest`
+        #[derive(Debug, Clone, PartialEq)]
+        pub enum Instr {
+            Copy { dest: Operand, src: Operand },
+            Binary { op: Op, ty: TinyType, dest: Operand, left: Operand, right: Operand },
+            Cast { to: TinyType, dest: Operand, src: Operand },
+            Label(usize),
+            Goto(usize),
+            // salta a la etiqueta si `left op right` es verdadero
+            CondGoto { op: Op, ty: TinyType, left: Operand, right: Operand, target: usize },
+            Read { ty: TinyType, dest: Operand },
+            Write { ty: TinyType, src: Operand },
+        }
 
-            fn emit_ro(&mut self, op: &str, r: i64, s: i64, t: i64, comment: &str){
-                println!("{}: {} {},{},{}", self.emit_loc, op, r, s, t);
-                self.emit_loc += 1;
-                self.emit_comment(comment);
-                if self.emit_loc > self.high_emit_loc {
-                    self.high_emit_loc = self.emit_loc;
+        #[derive(Debug, Clone, PartialEq)]
+        pub struct Quad {
+            pub instr: Instr,
+            // linea del programa fuente que genero la instruccion
+            pub line: u32,
+        }
+
+        #[derive(Debug, Clone)]
+        pub struct IrVar {
+            pub name: String,
+            pub ty: TinyType,
+            pub location: u32,
+        }
+
+        pub struct IrProgram {
+            pub code: Vec<Quad>,
+            pub temps: Vec<TinyType>,
+            pub vars: Vec<IrVar>,
+            pub labels: usize,
+            // localidades de datos que ocupan las variables
+            pub mem_size: u32,
+        }
+
+        // Nombre con solo caracteres ASCII para los formatos de texto que no
+        // aceptan otros (WebAssembly, LLVM): `año` queda como `a.uf1.o`. Los
+        // identificadores de Tiny no tienen '.', asi que no hay choques.
+        pub fn ascii_name(name: &str) -> String {
+            let mut out = String::new();
+            for c in name.chars() {
+                if c.is_ascii() {
+                    out.push(c);
+                } else {
+                    out.push_str(&format!(".u{:x}.", u32::from(c)));
                 }
             }
+            out
+        }
 
-            fn emit_skip(&mut self, many: i64) -> i64{
-                let mut i = self.emit_loc;
-                self.emit_loc += many;
-                if self.high_emit_loc < self.emit_loc {
-                    self.high_emit_loc = self.emit_loc;
+        impl IrProgram {
+            pub fn operand_type(&self, operand: &Operand) -> TinyType {
+                match operand {
+                    Operand::Temp(t) => self.temps[*t],
+                    Operand::Var(v) => self.vars[*v].ty,
+                    Operand::Int(_) => TinyType::Integer,
+                    Operand::Float(_) => TinyType::Float,
+                    Operand::Bool(_) => TinyType::Boolean,
                 }
-                return i;
             }
-    
-            fn emit_backup(&mut self, loc: i64){
-                if loc > self.high_emit_loc {
-                    self.emit_comment("BUG in emit backup");
-                    self.emit_loc = loc;
+
+            // Las variables con el mismo nombre en bloques distintos llevan su indice
+            pub fn var_name(&self, index: usize) -> String {
+                let name = &self.vars[index].name;
+                if self.vars.iter().filter(|v| &v.name == name).count() > 1 {
+                    format!("{}.{}", name, index)
+                } else {
+                    name.clone()
                 }
             }
 
-            fn emit_restore(&mut self){
-                self.emit_loc = self.high_emit_loc;
+            pub fn operand_text(&self, operand: &Operand) -> String {
+                match operand {
+                    Operand::Temp(t) => format!("t{}", t),
+                    Operand::Var(v) => self.var_name(*v),
+                    Operand::Int(n) => n.to_string(),
+                    Operand::Float(f) => format!("{:?}", f),
+                    Operand::Bool(b) => b.to_string(),
+                }
             }
 
-            fn emit_rm_abs(&mut self, op: &str, r: i64, a: i64, comment: &str){
-                println!("{}: {} {},{}({})", self.emit_loc, op, r, (a-(self.emit_loc-1)), 7);
-                self.emit_loc += 1;
-                self.emit_comment(comment);
-                if self.emit_loc > self.high_emit_loc {
-                    self.high_emit_loc = self.emit_loc;
+            pub fn instr_text(&self, instr: &Instr) -> String {
+                match instr {
+                    Instr::Copy { dest, src } => {
+                        format!("{} = {}", self.operand_text(dest), self.operand_text(src))
+                    }
+                    Instr::Binary { op, dest, left, right, .. } => format!(
+                        "{} = {} {} {}",
+                        self.operand_text(dest),
+                        self.operand_text(left),
+                        op.symbol(),
+                        self.operand_text(right)
+                    ),
+                    Instr::Cast { to, dest, src } => format!(
+                        "{} = {}({})",
+                        self.operand_text(dest),
+                        to.name(),
+                        self.operand_text(src)
+                    ),
+                    Instr::Label(label) => format!("L{}:", label),
+                    Instr::Goto(label) => format!("goto L{}", label),
+                    Instr::CondGoto { op, left, right, target, .. } => format!(
+                        "if {} {} {} goto L{}",
+                        self.operand_text(left),
+                        op.symbol(),
+                        self.operand_text(right),
+                        target
+                    ),
+                    Instr::Read { dest, .. } => format!("read {}", self.operand_text(dest)),
+                    Instr::Write { src, .. } => format!("write {}", self.operand_text(src)),
                 }
             }
-    
-            fn code_gen_helper(&mut self, node: &TreeNode, st: &mut SymbolTable){
-                match node.statement_type {
-                    StatementType::Program => {
-                        for child in &node.nodes {
-                            self.code_gen_helper(child, st);
+
+            // Texto de `--emit=ir`
+            pub fn to_text(&self) -> String {
+                let mut out = String::new();
+                for (index, var) in self.vars.iter().enumerate() {
+                    out.push_str(&format!(
+                        "var {}: {} @{}\n",
+                        self.var_name(index),
+                        var.ty.name(),
+                        var.location
+                    ));
+                }
+                for (index, ty) in self.temps.iter().enumerate() {
+                    out.push_str(&format!("temp t{}: {}\n", index, ty.name()));
+                }
+                for quad in &self.code {
+                    match quad.instr {
+                        Instr::Label(_) => out.push_str(&self.instr_text(&quad.instr)),
+                        _ => {
+                            out.push_str("    ");
+                            out.push_str(&self.instr_text(&quad.instr));
                         }
+                    }
+                    out.push('\n');
+                }
+                out
+            }
+        }
+
+        struct Lowering {
+            program: IrProgram,
+            line: u32,
+        }
+
+        impl Lowering {
+            fn emit(&mut self, instr: Instr) {
+                self.program.code.push(Quad { instr, line: self.line });
+            }
+
+            fn new_temp(&mut self, ty: TinyType) -> Operand {
+                self.program.temps.push(ty);
+                Operand::Temp(self.program.temps.len() - 1)
+            }
+
+            fn new_label(&mut self) -> usize {
+                self.program.labels += 1;
+                self.program.labels - 1
+            }
+
+            fn set_line(&mut self, node: &TreeNode) {
+                if node.token.line > 0 {
+                    self.line = node.token.line;
+                }
+            }
+
+            // una variable no declarada ya se reporto, se usa 0 en su lugar
+            fn var(&self, node: &TreeNode) -> Operand {
+                node.symbol.map_or(Operand::Int(0), Operand::Var)
+            }
+
+            fn expr(&mut self, node: &TreeNode) -> Operand {
+                match node.statement_type {
+                    StatementType::Literal => match node.val_type {
+                        TinyType::Float => Operand::Float(node.float_value()),
+                        _ => Operand::Int(node.int_value()),
                     },
-                    StatementType::Sequence => {
-                        for child in &node.nodes {
-                            self.code_gen_helper(child, st);
+                    StatementType::LiteralBoolExp => Operand::Bool(node.token.lexema == "true"),
+                    StatementType::Variable => self.var(node),
+                    StatementType::Arithmetic | StatementType::Relational => {
+                        let left = self.expr(&node.nodes[0]);
+                        let right = self.expr(&node.nodes[1]);
+                        let op = Op::from_token(&node.token.token).unwrap_or(Op::Add);
+                        let ty = self.program.operand_type(&left);
+                        let dest = self.new_temp(node.val_type);
+                        self.set_line(node);
+                        self.emit(Instr::Binary { op, ty, dest, left, right });
+                        dest
+                    }
+                    StatementType::Cast => {
+                        let src = self.expr(&node.nodes[0]);
+                        if self.program.operand_type(&src) == node.val_type {
+                            return src;
                         }
-                    },
-                    StatementType::VariableSeq => {
+                        let dest = self.new_temp(node.val_type);
+                        self.emit(Instr::Cast { to: node.val_type, dest, src });
+                        dest
+                    }
+                    StatementType::Not | StatementType::BooleanExp => {
+                        let dest = self.new_temp(TinyType::Boolean);
+                        let false_label = self.new_label();
+                        let end_label = self.new_label();
+                        self.cond(node, false, false_label);
+                        self.emit(Instr::Copy { dest, src: Operand::Bool(true) });
+                        self.emit(Instr::Goto(end_label));
+                        self.emit(Instr::Label(false_label));
+                        self.emit(Instr::Copy { dest, src: Operand::Bool(false) });
+                        self.emit(Instr::Label(end_label));
+                        dest
+                    }
+                    _ => Operand::Int(0),
+                }
+            }
+
+            // salta a `label` cuando la condicion vale `when`, si no continua
+            fn cond(&mut self, node: &TreeNode, when: bool, label: usize) {
+                match node.statement_type {
+                    StatementType::Not => self.cond(&node.nodes[0], !when, label),
+                    StatementType::BooleanExp => {
+                        // and termina con el primer falso, or con el primer verdadero
+                        let short_circuit = node.token.token == TokenType::TK_OR;
+                        if short_circuit == when {
+                            self.cond(&node.nodes[0], when, label);
+                            self.cond(&node.nodes[1], when, label);
+                        } else {
+                            let skip = self.new_label();
+                            self.cond(&node.nodes[0], short_circuit, skip);
+                            self.cond(&node.nodes[1], when, label);
+                            self.emit(Instr::Label(skip));
+                        }
+                    }
+                    StatementType::LiteralBoolExp => {
+                        if (node.token.lexema == "true") == when {
+                            self.emit(Instr::Goto(label));
+                        }
+                    }
+                    StatementType::Relational => {
+                        let left = self.expr(&node.nodes[0]);
+                        let right = self.expr(&node.nodes[1]);
+                        let mut op = Op::from_token(&node.token.token).unwrap_or(Op::Eq);
+                        if !when {
+                            op = op.negate();
+                        }
+                        let ty = self.program.operand_type(&left);
+                        self.emit(Instr::CondGoto { op, ty, left, right, target: label });
+                    }
+                    _ => {
+                        let value = self.expr(node);
+                        let op = if when { Op::Ne } else { Op::Eq };
+                        self.emit(Instr::CondGoto {
+                            op,
+                            ty: TinyType::Boolean,
+                            left: value,
+                            right: Operand::Bool(false),
+                            target: label,
+                        });
+                    }
+                }
+            }
+
+            fn stmt(&mut self, node: &TreeNode) {
+                self.set_line(node);
+                match node.statement_type {
+                    StatementType::Program | StatementType::Sequence | StatementType::VariableSeq => {
                         for child in &node.nodes {
-                            self.code_gen_helper(child, st);
+                            self.stmt(child);
                         }
-                    },
+                    }
+                    StatementType::ListVariableDec => {
+                        for var in &node.nodes {
+                            if let Some(init) = var.nodes.first() {
+                                self.set_line(var);
+                                let src = self.expr(init);
+                                let dest = self.var(var);
+                                self.emit(Instr::Copy { dest, src });
+                            }
+                        }
+                    }
                     StatementType::If => {
-                        self.emit_comment("-> if");
-                        self.code_gen_helper(&node.nodes[0], st);
-                        let mut saved_loc_1 = self.emit_skip(1);
-                        self.emit_comment("if: jump to else belongs here");
-
-                        self.code_gen_helper(&node.nodes[1], st);
-                        let mut saved_loc_2 = self.emit_skip(1);
-                        let mut current_loc = self.emit_skip(0);
-                        self.emit_backup(saved_loc_1);
-                        self.emit_rm_abs("JEQ", 0, current_loc, "jmp to end");
-                        self.emit_restore();
-
-                        self.code_gen_helper(&node.nodes[2], st);
-                        current_loc = self.emit_skip(0);
-                        self.emit_backup(saved_loc_2);
-                        self.emit_rm_abs("LDA", 7, current_loc, "jmp to end");
-                        self.emit_restore();
-                        self.emit_comment("<- if");
-                    },
+                        let else_label = self.new_label();
+                        self.cond(&node.nodes[0], false, else_label);
+                        self.stmt(&node.nodes[1]);
+                        if node.nodes[2].statement_type == StatementType::NoType {
+                            self.emit(Instr::Label(else_label));
+                        } else {
+                            let end_label = self.new_label();
+                            self.emit(Instr::Goto(end_label));
+                            self.emit(Instr::Label(else_label));
+                            self.stmt(&node.nodes[2]);
+                            self.emit(Instr::Label(end_label));
+                        }
+                    }
                     StatementType::Repeat => {
-                        self.emit_comment("-> repeat");
-                        let mut saved_loc_1 = self.emit_skip(0);
-                        self.emit_comment("repeat: jump after body comes back here");
-                        self.code_gen_helper(&node.nodes[1], st);
-                        self.code_gen_helper(&node.nodes[0], st);
-                        self.emit_rm_abs("JEQ", 0, saved_loc_1, "repeat: jmp back to body");
-                        self.emit_comment("<- repeat");
-                    },
+                        let top_label = self.new_label();
+                        self.emit(Instr::Label(top_label));
+                        self.stmt(&node.nodes[1]);
+                        self.set_line(node);
+                        self.cond(&node.nodes[0], false, top_label);
+                    }
                     StatementType::While => {
-                        self.emit_comment("-> while");
-                        let mut saved_loc_1 = self.emit_skip(1);
-                        self.code_gen_helper(&node.nodes[0], st);
-                        self.emit_comment("while: jmp here for check");
-                        let mut current_loc = self.emit_skip(0);
-                        self.code_gen_helper(&node.nodes[1], st);                        
-                        self.emit_comment("<- while");
-                    },
+                        let top_label = self.new_label();
+                        let end_label = self.new_label();
+                        self.emit(Instr::Label(top_label));
+                        self.cond(&node.nodes[0], false, end_label);
+                        self.stmt(&node.nodes[1]);
+                        self.set_line(node);
+                        self.emit(Instr::Goto(top_label));
+                        self.emit(Instr::Label(end_label));
+                    }
                     StatementType::Assignment => {
-                        self.emit_comment("-> assign");
-                        self.code_gen_helper(&node.nodes[1], st);
-                        let mut loc = st.lookup_no_decl(&node.nodes[0].token.lexema).unwrap().mem_location;
-                        self.emit_rm("ST", 0, loc.into(), 5, "assign: store value");
-                        self.emit_comment("<- assign");
-                    },
+                        let src = self.expr(&node.nodes[1]);
+                        let dest = self.var(&node.nodes[0]);
+                        self.emit(Instr::Copy { dest, src });
+                    }
                     StatementType::Read => {
-                        self.emit_ro("IN", 0, 0, 0, "read value");
-                        let loc = st.lookup_no_decl(&node.nodes[0].token.lexema).unwrap().mem_location;
-                        self.emit_rm("ST", 0, loc.into(), 5, "read: store value");
-                    },
+                        let dest = self.var(&node.nodes[0]);
+                        let ty = self.program.operand_type(&dest);
+                        self.emit(Instr::Read { ty, dest });
+                    }
                     StatementType::Write => {
-                        self.code_gen_helper(&node.nodes[0], st);
-                        self.emit_ro("OUT", 0, 0, 0, "write ac");
-                    },
-                    StatementType::LiteralBoolExp => {
-                        self.emit_comment("-> literal boolean");
-                        let value: &str = &node.token.lexema;
-                        match value {
-                            "true" => {
-                                self.emit_rm("LDC", 0, 1, 0, "load const true value");
-                            },
-                            "false" => {
-                                self.emit_rm("LDC", 0, 0, 0, "load const false value");
-                            }
-                            _ =>{}
-                        }
-                        self.emit_comment("<- literal boolean");
-                    },
-                    StatementType::Literal => {
-                        self.emit_comment("-> const");
-                        self.emit_rm("LDC", 0, node.token.lexema.parse().unwrap_or(0), 0, "load const");
-                        self.emit_comment("<- const");
-                    },
-                    StatementType::Variable => {
-                        self.emit_comment("-> id");
-                        let loc: u32 = st.lookup_no_decl(&node.token.lexema).unwrap().mem_location;
-                        self.emit_rm("LD", 0, loc.into(), 5, "load id value");
-                        self.emit_comment("<- id");
-                    },
-                    StatementType::Not => {
-                        self.emit_comment("-> bool expression");
-                        self.code_gen_helper(&node.nodes[0], st);
-                        self.emit_rm("ST", 0, self.tmp_offset+1, 6, "bool op: push left");
-                        self.tmp_offset += 1;
-                        self.emit_ro("NOT", 0, 1, 0, "bool op: not");
-                        self.emit_comment("<- bool expression");
-                    },
-                    StatementType::BooleanExp => {
-                        self.emit_comment("-> bool expression");
-                        self.code_gen_helper(&node.nodes[0], st);
-                        self.emit_rm("ST", 0, self.tmp_offset-1, 6, "bool op: push left");
-                        self.code_gen_helper(&node.nodes[1], st);
-                        self.emit_rm("LD", 1, self.tmp_offset+1, 6, "bool op: push right");
-                        self.tmp_offset += 1;
-                        match node.token.token {
-                            TokenType::TK_AND => {
-                                self.emit_ro("AND", 0, 1, 0, "bool op: and");
-                            },
-                            TokenType::TK_OR => {
-                                self.emit_ro("OR", 0, 1, 0, "bool op: or");
-                            }
-                            _ => {}
-                        }
-                        self.emit_comment("-> bool expression");
-                    },
-                    StatementType::Arithmetic => {
-                        self.emit_comment("-> op");
-                        self.code_gen_helper(&node.nodes[0], st);
-                        self.emit_rm("ST", 0, self.tmp_offset-1, 6, "op: push left");
-                        self.code_gen_helper(&node.nodes[1], st);
-                        self.emit_rm("LD", 1, self.tmp_offset+1, 6, "op:push right");
-                        self.tmp_offset += 1;
-                        match node.token.token {
-                            TokenType::TK_PLUS => {
-                                self.emit_ro("ADD", 0, 1, 0, "op: +");
-                            },
-                            TokenType::TK_MINUS => {
-                                self.emit_ro("SUB", 0, 1, 0, "op: -");
-                            },
-                            TokenType::TK_TIMES => {
-                                self.emit_ro("MUL", 0, 1, 0, "op: *");
-                            },
-                            TokenType::TK_OVER => {
-                                self.emit_ro("DIV", 0, 1, 0, "op: /");
-                            }
-                            _ => {}
-                        }
-                        self.emit_comment("<- op");
-                    },
-                    StatementType::Relational => {
-                        self.emit_comment("-> rel");
-                        self.code_gen_helper(&node.nodes[0], st);
-                        self.emit_rm("ST", 0, self.tmp_offset-1, 6, "rel: push left");
-                        self.code_gen_helper(&node.nodes[1], st);
-                        self.emit_rm("LD", 1, self.tmp_offset+1, 6, "rel:push right");
-                        self.tmp_offset += 1;
-                        match node.token.token {
-                            TokenType::TK_LT => {
-                                self.emit_ro("SUB", 0, 1, 0, "op <");
-                                self.emit_rm("JLT", 0, 2, 7, "br if true");
-                                self.emit_rm("LDC", 0, 0, 0, "false case");
-                                self.emit_rm("LDA", 7, 1, 7, "unconditional jmp");
-                                self.emit_rm("LDC", 0, 1, 0, "true case");
-                            },
-                            TokenType::TK_LTE => {
-                                self.emit_ro("SUB", 0, 1, 0, "op <=");
-                                self.emit_rm("JLE", 0, 2, 7, "br if true");
-                                self.emit_rm("LDC", 0, 0, 0, "false case");
-                                self.emit_rm("LDA", 7, 1, 7, "unconditional jmp");
-                                self.emit_rm("LDC", 0, 1, 0, "true case");
-                            },
-                            TokenType::TK_GT => {
-                                self.emit_ro("SUB", 0, 1, 0, "op >");
-                                self.emit_rm("JGT", 0, 2, 7, "br if true");
-                                self.emit_rm("LDC", 0, 0, 0, "false case");
-                                self.emit_rm("LDA", 7, 1, 7, "unconditional jmp");
-                                self.emit_rm("LDC", 0, 1, 0, "true case");
-                            },
-                            TokenType::TK_GTE => {
-                                self.emit_ro("SUB", 0, 1, 0, "op >=");
-                                self.emit_rm("JGE", 0, 2, 7, "br if true");
-                                self.emit_rm("LDC", 0, 0, 0, "false case");
-                                self.emit_rm("LDA", 7, 1, 7, "unconditional jmp");
-                                self.emit_rm("LDC", 0, 1, 0, "true case");
-                            },
-                            TokenType::TK_EQ => {
-                                self.emit_ro("SUB", 0, 1, 0, "op ==");
-                                self.emit_rm("JEQ", 0, 2, 7, "br if true");
-                                self.emit_rm("LDC", 0, 0, 0, "false case");
-                                self.emit_rm("LDA", 7, 1, 7, "unconditional jmp");
-                                self.emit_rm("LDC", 0, 1, 0, "true case");
-                            },
-                            TokenType::TK_DIF => {
-                                self.emit_ro("SUB", 0, 1, 0, "op <");
-                                self.emit_rm("JNE", 0, 2, 7, "br if true");
-                                self.emit_rm("LDC", 0, 0, 0, "false case");
-                                self.emit_rm("LDA", 7, 1, 7, "unconditional jmp");
-                                self.emit_rm("LDC", 0, 1, 0, "true case");
-                            },
-                            _ => {}
-                        }
-                        self.emit_comment("<- op");
-                    },
+                        let src = self.expr(&node.nodes[0]);
+                        let ty = self.program.operand_type(&src);
+                        self.emit(Instr::Write { ty, src });
+                    }
                     _ => {}
                 }
             }
         }
+
+        // Traduce el arbol revisado a codigo de tres direcciones
+        pub fn lower(node: &TreeNode, st: &SymbolTable) -> IrProgram {
+            let vars = st
+                .iter()
+                .map(|b| IrVar {
+                    name: b.name().to_string(),
+                    ty: b.data_type(),
+                    location: b.mem_location(),
+                })
+                .collect();
+            let mut lowering = Lowering {
+                program: IrProgram {
+                    code: Vec::new(),
+                    temps: Vec::new(),
+                    vars,
+                    labels: 0,
+                    mem_size: st.mem_size,
+                },
+                line: 0,
+            };
+            lowering.stmt(node);
+            lowering.program
+        }
     }
 
-    pub mod analyzer {
-        use super::BucketList;
-        use super::StatementType;
-        use super::TinyType;
-        use super::TreeNode;
+    // Grafo de flujo de control: bloques basicos del codigo intermedio
+    pub mod cfg {
+        use super::ir::{Instr, IrProgram};
         use std::collections::HashMap;
 
-        pub struct SymbolTable {
-            pub table: HashMap<String, BucketList>,
-            pub init_mem: u32,
+        pub struct BasicBlock {
+            // instrucciones `start..end` de IrProgram::code
+            pub start: usize,
+            pub end: usize,
+            // si el bloque termina en un salto condicional, el primero es el
+            // destino del salto y el segundo la instruccion siguiente
+            pub succs: Vec<usize>,
+            pub preds: Vec<usize>,
         }
 
-        impl SymbolTable {
-            pub fn new() -> SymbolTable {
-                return SymbolTable {
-                    table: HashMap::new(),
-                    init_mem: 0,
-                };
+        // El bloque 0 es la entrada y el ultimo es la salida, que no tiene instrucciones
+        pub struct Cfg {
+            pub blocks: Vec<BasicBlock>,
+        }
+
+        impl Cfg {
+            pub fn entry(&self) -> usize {
+                0
             }
 
-            pub fn insert(&mut self, name: &str, lineno: u32, loc: u32, data_type: TinyType) {
-                if !self.table.contains_key(name) {
-                    let new_variable: BucketList = BucketList {
-                        lines: vec![lineno],
-                        mem_location: loc,
-                        data_type: data_type,
-                    };
-                    self.table.insert(String::from(name), new_variable);
+            pub fn exit(&self) -> usize {
+                self.blocks.len() - 1
+            }
+
+            // bloques a los que se llega desde la entrada
+            pub fn reachable(&self) -> Vec<bool> {
+                let mut reachable = vec![false; self.blocks.len()];
+                let mut pending = vec![self.entry()];
+                while let Some(block) = pending.pop() {
+                    if reachable[block] {
+                        continue;
+                    }
+                    reachable[block] = true;
+                    pending.extend(self.blocks[block].succs.iter().copied());
+                }
+                reachable
+            }
+
+            fn node_name(&self, block: usize) -> String {
+                if block == self.exit() {
+                    String::from("exit")
                 } else {
-                    self.declarationError(name, lineno, "variable double declaration");
+                    format!("B{}", block)
                 }
             }
 
-            pub fn build_table(&mut self, node: &TreeNode) {
-                match node.statement_type {
-                    StatementType::Program
-                    | StatementType::VariableSeq
-                    | StatementType::Sequence
-                    | StatementType::If
-                    | StatementType::Repeat
-                    | StatementType::While
-                    | StatementType::Read
-                    | StatementType::Write
-                    | StatementType::Not => {
-                        for nodes in &node.nodes {
-                            self.build_table(nodes);
-                        }
+            // Texto de `--emit=cfg-dot` para Graphviz
+            pub fn to_dot(&self, program: &IrProgram) -> String {
+                let mut out = String::from("digraph cfg {\n");
+                out.push_str("    node [shape=box, fontname=\"monospace\"];\n");
+                out.push_str("    entry [shape=oval];\n");
+                out.push_str("    exit [shape=oval];\n");
+                out.push_str(&format!("    entry -> {};\n", self.node_name(self.entry())));
+                for (index, block) in self.blocks.iter().enumerate() {
+                    if index == self.exit() {
+                        continue;
                     }
-                    StatementType::ListVariableDec => {
-                        for nodes in &node.nodes {
-                            self.insert(
-                                &nodes.token.lexema,
-                                nodes.token.line,
-                                self.init_mem,
-                                node.val_type,
-                            );
-                            self.init_mem += 1;
-                        }
+                    let mut label = format!("B{}\\l", index);
+                    for quad in &program.code[block.start..block.end] {
+                        label.push_str(&program.instr_text(&quad.instr).replace('"', "\\\""));
+                        label.push_str("\\l");
                     }
-                    StatementType::Assignment
-                    | StatementType::Arithmetic
-                    | StatementType::Relational
-                    | StatementType::BooleanExp => {
-                        self.build_table(&node.nodes[0]);
-                        self.build_table(&node.nodes[1]);
+                    out.push_str(&format!("    B{} [label=\"{}\"];\n", index, label));
+                    let branch = match program.code[block.start..block.end].last() {
+                        Some(quad) => matches!(quad.instr, Instr::CondGoto { .. }),
+                        None => false,
+                    };
+                    for (n, succ) in block.succs.iter().enumerate() {
+                        let attrs = match (branch, n) {
+                            (true, 0) => " [label=\"true\"]",
+                            (true, _) => " [label=\"false\"]",
+                            _ => "",
+                        };
+                        out.push_str(&format!("    B{} -> {}{};\n", index, self.node_name(*succ), attrs));
                     }
-                    StatementType::Variable => {
-                        self.lookup(&node.token.lexema, node.token.line);
+                }
+                out.push_str("}\n");
+                out
+            }
+        }
+
+        pub fn build(program: &IrProgram) -> Cfg {
+            let code = &program.code;
+            // inicio de cada bloque: la primera instruccion, cada grupo de
+            // etiquetas seguidas y la instruccion que sigue a un salto
+            let mut leaders = vec![0];
+            for (index, quad) in code.iter().enumerate() {
+                match quad.instr {
+                    Instr::Label(_) if index == 0 || !matches!(code[index - 1].instr, Instr::Label(_)) => {
+                        leaders.push(index)
                     }
+                    Instr::Goto(_) | Instr::CondGoto { .. } => leaders.push(index + 1),
                     _ => {}
                 }
             }
+            leaders.retain(|&index| index < code.len());
+            leaders.sort_unstable();
+            leaders.dedup();
+
+            let mut blocks: Vec<BasicBlock> = Vec::new();
+            let mut label_block = HashMap::new();
+            for (n, &start) in leaders.iter().enumerate() {
+                let end = leaders.get(n + 1).copied().unwrap_or(code.len());
+                for quad in &code[start..end] {
+                    match quad.instr {
+                        Instr::Label(label) => label_block.insert(label, blocks.len()),
+                        _ => break,
+                    };
+                }
+                blocks.push(BasicBlock { start, end, succs: Vec::new(), preds: Vec::new() });
+            }
+            if blocks.is_empty() {
+                blocks.push(BasicBlock { start: 0, end: 0, succs: Vec::new(), preds: Vec::new() });
+            }
+            let exit = blocks.len();
+            blocks.push(BasicBlock { start: code.len(), end: code.len(), succs: Vec::new(), preds: Vec::new() });
+
+            for index in 0..exit {
+                let block = &blocks[index];
+                let next = index + 1;
+                let succs = match block.end.checked_sub(1).filter(|&last| last >= block.start).map(|last| &code[last].instr) {
+                    Some(Instr::Goto(label)) => vec![label_block[label]],
+                    Some(Instr::CondGoto { target, .. }) => vec![label_block[target], next],
+                    _ => vec![next],
+                };
+                for &succ in &succs {
+                    if !blocks[succ].preds.contains(&index) {
+                        blocks[succ].preds.push(index);
+                    }
+                }
+                blocks[index].succs = succs;
+            }
+            Cfg { blocks }
+        }
+    }
+
+    // Asignacion de registros a los temporales del codigo intermedio
+    pub mod regalloc {
+        use super::ir::{Instr, IrProgram, Operand};
+
+        // Registros de TM libres para temporales: 0 y 1 son los acumuladores,
+        // 5 apunta a las variables, 6 a los temporales en memoria y 7 es el pc
+        pub const REGISTERS: [i64; 3] = [2, 3, 4];
+
+        fn temps_of(instr: &Instr) -> Vec<usize> {
+            let operands = match instr {
+                Instr::Copy { dest, src } | Instr::Cast { dest, src, .. } => vec![*dest, *src],
+                Instr::Binary { dest, left, right, .. } => vec![*dest, *left, *right],
+                Instr::CondGoto { left, right, .. } => vec![*left, *right],
+                Instr::Read { dest, .. } => vec![*dest],
+                Instr::Write { src, .. } => vec![*src],
+                Instr::Label(_) | Instr::Goto(_) => vec![],
+            };
+            operands
+                .into_iter()
+                .filter_map(|operand| match operand {
+                    Operand::Temp(t) => Some(t),
+                    _ => None,
+                })
+                .collect()
+        }
+
+        // Cada temporal vive desde su primera hasta su ultima aparicion en el
+        // codigo. Los temporales se crean por expresion, asi que ninguno sigue
+        // vivo al regresar al inicio de un ciclo.
+        fn live_intervals(program: &IrProgram) -> Vec<Option<(usize, usize)>> {
+            let mut intervals: Vec<Option<(usize, usize)>> = vec![None; program.temps.len()];
+            for (index, quad) in program.code.iter().enumerate() {
+                for t in temps_of(&quad.instr) {
+                    intervals[t] = Some(match intervals[t] {
+                        Some((start, _)) => (start, index),
+                        None => (index, index),
+                    });
+                }
+            }
+            intervals
+        }
+
+        // Linear scan sobre los intervalos de vida. Cuando no hay registro
+        // libre se deja en memoria el que termina mas tarde.
+        pub fn linear_scan(program: &IrProgram) -> Vec<Option<i64>> {
+            let intervals = live_intervals(program);
+            let mut order: Vec<usize> = (0..intervals.len()).filter(|&t| intervals[t].is_some()).collect();
+            order.sort_by_key(|&t| intervals[t]);
+
+            let mut assigned = vec![None; program.temps.len()];
+            let mut free: Vec<i64> = REGISTERS.iter().rev().copied().collect();
+            // temporales con registro, ordenados por el final de su intervalo
+            let mut active: Vec<usize> = Vec::new();
+            for t in order {
+                let (start, end) = intervals[t].unwrap();
+                // un temporal que termina donde empieza otro ya se cargo en
+                // un acumulador, asi que su registro se puede reutilizar
+                while let Some(&first) = active.first() {
+                    if intervals[first].unwrap().1 > start {
+                        break;
+                    }
+                    active.remove(0);
+                    free.push(assigned[first].unwrap());
+                }
+                let reg = match free.pop() {
+                    Some(reg) => reg,
+                    None => {
+                        let last = *active.last().unwrap();
+                        if intervals[last].unwrap().1 <= end {
+                            continue;
+                        }
+                        active.pop();
+                        assigned[last].take().unwrap()
+                    }
+                };
+                assigned[t] = Some(reg);
+                let position = active
+                    .iter()
+                    .position(|&a| intervals[a].unwrap().1 > end)
+                    .unwrap_or(active.len());
+                active.insert(position, t);
+            }
+            assigned
+        }
+
+        // Localidad -d(6) de cada temporal sin registro (`registers` puede
+        // estar vacio si no corrio regalloc). Una localidad se reutiliza cuando
+        // termina el intervalo del temporal que la tenia, asi la memoria de
+        // temporales no crece con el largo del programa.
+        pub fn spill_slots(program: &IrProgram, registers: &[Option<i64>]) -> Vec<Option<i64>> {
+            let intervals = live_intervals(program);
+            let mut order: Vec<usize> = (0..intervals.len())
+                .filter(|&t| intervals[t].is_some() && registers.get(t).copied().flatten().is_none())
+                .collect();
+            order.sort_by_key(|&t| intervals[t]);
+
+            let mut slots = vec![None; program.temps.len()];
+            let mut free: Vec<i64> = Vec::new();
+            let mut used = 0;
+            // temporales con localidad que siguen vivos
+            let mut active: Vec<usize> = Vec::new();
+            for t in order {
+                let (start, _) = intervals[t].unwrap();
+                // como con los registros, los operandos se cargan antes de
+                // guardar el resultado
+                active.retain(|&a| {
+                    let ended = intervals[a].unwrap().1 <= start;
+                    if ended {
+                        free.push(slots[a].unwrap());
+                    }
+                    !ended
+                });
+                // la localidad libre mas alta, para que el bloque quede compacto
+                free.sort_unstable();
+                let slot = free.pop().unwrap_or_else(|| {
+                    used += 1;
+                    1 - used
+                });
+                slots[t] = Some(slot);
+                active.push(t);
+            }
+            slots
+        }
+
+        // Registro o localidad de cada temporal, una linea por temporal
+        pub fn to_text(program: &IrProgram, registers: &[Option<i64>]) -> String {
+            let slots = spill_slots(program, registers);
+            let mut out = String::new();
+            for (t, slot) in slots.iter().enumerate() {
+                match (registers.get(t).copied().flatten(), slot) {
+                    (Some(reg), _) => out.push_str(&format!("t{} -> r{}\n", t, reg)),
+                    (None, Some(slot)) => out.push_str(&format!("t{} -> {}(6)\n", t, slot)),
+                    (None, None) => {}
+                }
+            }
+            out
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use super::*;
+            use crate::compiler::codegen::CodeGenResult;
+            use crate::compiler::ir::{Op, Quad};
+            use crate::compiler::tm;
+            use crate::compiler::TinyType;
+            use crate::common::samples;
+            use crate::tests::{lower, lower_source};
+
+            // Dos temporales con el mismo registro no pueden estar vivos a la
+            // vez; uno puede empezar donde termina el otro
+            fn check_allocation(program: &IrProgram, registers: &[Option<i64>]) {
+                let intervals = live_intervals(program);
+                for (a, reg) in registers.iter().enumerate() {
+                    let reg = match reg {
+                        Some(reg) => reg,
+                        None => continue,
+                    };
+                    assert!(REGISTERS.contains(reg), "t{} got r{}", a, reg);
+                    let (start, end) = intervals[a].unwrap();
+                    for b in a + 1..registers.len() {
+                        if registers[b] == Some(*reg) {
+                            let (other_start, other_end) = intervals[b].unwrap();
+                            assert!(end <= other_start || other_end <= start, "t{} and t{} share r{}", a, b, reg);
+                        }
+                    }
+                }
+            }
+
+            // Lo mismo para las localidades de los temporales en memoria
+            fn check_slots(program: &IrProgram, registers: &[Option<i64>]) {
+                let intervals = live_intervals(program);
+                let slots = spill_slots(program, registers);
+                for (a, slot) in slots.iter().enumerate() {
+                    let slot = match slot {
+                        Some(slot) => slot,
+                        None => continue,
+                    };
+                    assert!(registers.get(a).copied().flatten().is_none(), "t{} has a register and a slot", a);
+                    let (start, end) = intervals[a].unwrap();
+                    for b in a + 1..slots.len() {
+                        if slots[b] == Some(*slot) {
+                            let (other_start, other_end) = intervals[b].unwrap();
+                            assert!(end <= other_start || other_end <= start, "t{} and t{} share {}(6)", a, b, slot);
+                        }
+                    }
+                }
+            }
+
+            fn run(program: &IrProgram, registers: Vec<Option<i64>>, input: &str) -> (String, u32) {
+                let mut code_gen = CodeGenResult::new();
+                code_gen.set_registers(registers);
+                code_gen.generate(program);
+                let object = code_gen.to_object();
+                (tm::run(&object.code, input), object.data_size)
+            }
+
+            // `statements` veces una expresion con cinco temporales vivos a la vez
+            fn nested(statements: usize) -> String {
+                let mut source = String::from("program {\n    int a, b, c, d;\n    read a;\n    read b;\n    read c;\n    read d;\n");
+                for _ in 0..statements {
+                    source.push_str("    write (a * b) + ((c * d) + ((a * c) + ((b * d) + (a * d))));\n");
+                }
+                source.push_str("}\n");
+                source
+            }
+
+            // t0..t4 estan vivos al mismo tiempo: con tres registros dos se
+            // quedan en memoria
+            #[test]
+            fn spills_when_registers_run_out() {
+                // t0 = 1 .. t4 = 5; t8 = t3 + t4; t7 = t2 + t8; ...; write t5
+                let temp = Operand::Temp;
+                let mut code: Vec<Instr> = (0..5)
+                    .map(|t| Instr::Copy { dest: temp(t), src: Operand::Int(t as i32 + 1) })
+                    .collect();
+                for t in (0..4).rev() {
+                    let rest = if t == 3 { temp(4) } else { temp(t + 6) };
+                    let ty = TinyType::Integer;
+                    code.push(Instr::Binary { op: Op::Add, ty, dest: temp(t + 5), left: temp(t), right: rest });
+                }
+                code.push(Instr::Write { ty: TinyType::Integer, src: temp(5) });
+                let program = IrProgram {
+                    code: code.into_iter().map(|instr| Quad { instr, line: 1 }).collect(),
+                    temps: vec![TinyType::Integer; 9],
+                    vars: vec![],
+                    labels: 0,
+                    mem_size: 0,
+                };
+                let registers = linear_scan(&program);
+                check_allocation(&program, &registers);
+                assert!(registers.iter().filter(|reg| reg.is_none()).count() >= 2, "{:?}", registers);
+                assert_eq!(run(&program, registers, "").0, "15\n");
+                assert_eq!(run(&program, vec![None; 9], "").0, "15\n");
+            }
+
+            // La memoria de temporales depende de cuantos estan vivos a la vez,
+            // no de cuantos tiene el programa
+            #[test]
+            fn spill_slots_are_reused() {
+                let short = lower_source("spill_short", &nested(1));
+                let long = lower_source("spill_long", &nested(20));
+                assert!(long.temps.len() > 20 * REGISTERS.len());
+                let mut sizes = Vec::new();
+                for program in [&short, &long].iter() {
+                    let registers = linear_scan(program);
+                    assert!(registers.iter().filter(|reg| reg.is_none()).count() >= 2, "{:?}", registers);
+                    check_slots(program, &registers);
+                    check_slots(program, &[]);
+
+                    let statements = program.temps.len() / short.temps.len();
+                    let expected = "59\n".repeat(statements);
+                    let (output, with_registers) = run(program, registers, "2 3 4 5");
+                    assert_eq!(output, expected);
+                    let (output, in_memory) = run(program, vec![], "2 3 4 5");
+                    assert_eq!(output, expected);
+                    sizes.push((with_registers, in_memory));
+                }
+                assert_eq!(sizes[0], sizes[1]);
+                // 4 variables y a lo mas 2 y 5 temporales en memoria
+                assert!(sizes[0].0 <= 6 && sizes[0].1 <= 9, "{:?}", sizes);
+            }
+
+            #[test]
+            fn samples_never_share_a_live_register() {
+                for sample in samples() {
+                    let program = lower(sample.path.to_str().unwrap());
+                    let registers = linear_scan(&program);
+                    check_allocation(&program, &registers);
+                    check_slots(&program, &registers);
+                    check_slots(&program, &[]);
+                }
+            }
+        }
+    }
+
+    // Instrucciones de la maquina TM. El formato de texto es el que imprime
+    // el generador y lee el simulador:
+    //   N: OP r,s,t      instrucciones de registro (RO)
+    //   N: OP r,d(s)     instrucciones de registro y memoria (RM)
+    // Las lineas que empiezan con ';' o '*' son comentarios y lo que sigue a
+    // los operandos de una instruccion se ignora.
+    pub mod tm {
+        use std::convert::TryFrom;
+        use std::fmt;
+
+        // 0 y 1 acumuladores, 5 variables, 6 memoria alta y 7 el pc
+        pub const NUM_REGS: i64 = 8;
+        pub const PC: i64 = 7;
+
+        pub const RO_OPS: [&str; 15] = [
+            "HALT", "IN", "OUT", "ADD", "SUB", "MUL", "DIV", "FADD", "FSUB", "FMUL", "FDIV", "ITOF", "FTOI", "FIN",
+            "FOUT",
+        ];
+        pub const RM_OPS: [&str; 10] = ["LD", "LDA", "LDC", "ST", "JLT", "JLE", "JGE", "JGT", "JEQ", "JNE"];
+
+        #[derive(Debug, Clone, PartialEq)]
+        pub enum TmInstruction {
+            // registro: r,s,t
+            Ro { op: &'static str, r: i64, s: i64, t: i64 },
+            // registro y memoria: r,d(s)
+            Rm { op: &'static str, r: i64, d: i64, s: i64 },
+        }
+
+        impl fmt::Display for TmInstruction {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                match self {
+                    TmInstruction::Ro { op, r, s, t } => write!(f, "{} {},{},{}", op, r, s, t),
+                    TmInstruction::Rm { op, r, d, s } => write!(f, "{} {},{}({})", op, r, d, s),
+                }
+            }
+        }
+
+        // Error al leer o validar un programa TM. `line` es la linea del
+        // texto al leer y la direccion de la instruccion al validar.
+        #[derive(Debug, Clone, PartialEq)]
+        pub struct TmError {
+            pub line: usize,
+            pub message: String,
+        }
+
+        impl fmt::Display for TmError {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "{}: {}", self.line, self.message)
+            }
+        }
+
+        fn number(text: &str) -> Result<i64, String> {
+            text.trim().parse().map_err(|_| format!("expected a number, found '{}'", text.trim()))
+        }
+
+        impl TmInstruction {
+            // Lee `OP r,s,t` o `OP r,d(s)`, sin la direccion
+            pub fn parse(text: &str) -> Result<TmInstruction, String> {
+                let text = text.trim();
+                let (name, operands) = text.split_at(text.find(char::is_whitespace).unwrap_or(text.len()));
+                let name = name.to_uppercase();
+                // los operandos terminan en el primer espacio que no esta junto
+                // a ',', '(' o ')'
+                let mut words = operands.split_whitespace();
+                let mut operands = String::from(words.next().unwrap_or(""));
+                for word in words {
+                    if operands.ends_with([',', '(']) || word.starts_with([',', '(', ')']) {
+                        operands.push_str(word);
+                    } else {
+                        break;
+                    }
+                }
+                let parts: Vec<&str> = operands.split(',').collect();
+                if let Some(op) = RO_OPS.iter().copied().find(|op| *op == name) {
+                    if parts.len() != 3 {
+                        return Err(format!("{} expects operands r,s,t", op));
+                    }
+                    return Ok(TmInstruction::Ro {
+                        op,
+                        r: number(parts[0])?,
+                        s: number(parts[1])?,
+                        t: number(parts[2])?,
+                    });
+                }
+                if let Some(op) = RM_OPS.iter().copied().find(|op| *op == name) {
+                    let memory = parts.get(1).and_then(|memory| {
+                        let open = memory.find('(')?;
+                        memory.strip_suffix(')').map(|memory| (&memory[..open], &memory[open + 1..]))
+                    });
+                    return match memory {
+                        Some((d, s)) if parts.len() == 2 => Ok(TmInstruction::Rm {
+                            op,
+                            r: number(parts[0])?,
+                            d: number(d)?,
+                            s: number(s)?,
+                        }),
+                        _ => Err(format!("{} expects operands r,d(s)", op)),
+                    };
+                }
+                Err(format!("unknown instruction '{}'", name))
+            }
+
+            pub fn op(&self) -> &'static str {
+                match self {
+                    TmInstruction::Ro { op, .. } | TmInstruction::Rm { op, .. } => op,
+                }
+            }
+
+            pub fn registers(&self) -> Vec<i64> {
+                match self {
+                    TmInstruction::Ro { r, s, t, .. } => vec![*r, *s, *t],
+                    TmInstruction::Rm { r, s, .. } => vec![*r, *s],
+                }
+            }
+
+            // Direccion a la que salta la instruccion en `loc`, si es un
+            // salto relativo al pc
+            pub fn jump_target(&self, loc: usize) -> Option<i64> {
+                match self {
+                    TmInstruction::Rm { op, r, d, s: PC } if op.starts_with('J') || (*op == "LDA" && *r == PC) => {
+                        Some(loc as i64 + 1 + d)
+                    }
+                    _ => None,
+                }
+            }
+        }
+
+        // Lee un programa TM. Cada instruccion queda en su direccion y los
+        // huecos se llenan con HALT 0,0,0, igual que en el simulador.
+        pub fn parse_program(text: &str) -> Result<Vec<TmInstruction>, TmError> {
+            let mut program: Vec<TmInstruction> = Vec::new();
+            for (index, line) in text.lines().enumerate() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with(';') || line.starts_with('*') {
+                    continue;
+                }
+                let error = |message: String| TmError { line: index + 1, message };
+                let colon = line.find(':').ok_or_else(|| error(String::from("expected 'N: instruction'")))?;
+                let loc: usize = line[..colon]
+                    .trim()
+                    .parse()
+                    .map_err(|_| error(format!("bad address '{}'", line[..colon].trim())))?;
+                let instr = TmInstruction::parse(&line[colon + 1..]).map_err(error)?;
+                if program.len() <= loc {
+                    program.resize(loc + 1, TmInstruction::Ro { op: "HALT", r: 0, s: 0, t: 0 });
+                }
+                program[loc] = instr;
+            }
+            Ok(program)
+        }
+
+        pub fn program_text(program: &[TmInstruction]) -> String {
+            let mut out = String::new();
+            for (loc, instr) in program.iter().enumerate() {
+                out.push_str(&format!("{}: {}\n", loc, instr));
+            }
+            out
+        }
+
+        // Revisa que las operaciones existan, que los registros esten entre
+        // 0 y 7, que los desplazamientos quepan en 32 bits y que los saltos
+        // relativos al pc caigan dentro del programa
+        pub fn validate(program: &[TmInstruction]) -> Vec<TmError> {
+            let mut errors = Vec::new();
+            for (loc, instr) in program.iter().enumerate() {
+                let error = |message: String| TmError { line: loc, message };
+                let (known, kind) = match instr {
+                    TmInstruction::Ro { op, .. } => (RO_OPS.contains(op), "RO"),
+                    TmInstruction::Rm { op, .. } => (RM_OPS.contains(op), "RM"),
+                };
+                if !known {
+                    errors.push(error(format!("'{}' is not a valid {} instruction", instr.op(), kind)));
+                }
+                for reg in instr.registers() {
+                    if !(0..NUM_REGS).contains(&reg) {
+                        errors.push(error(format!("register {} out of range in '{}'", reg, instr)));
+                    }
+                }
+                if let TmInstruction::Rm { d, .. } = instr {
+                    if i32::try_from(*d).is_err() {
+                        errors.push(error(format!("displacement {} out of range in '{}'", d, instr)));
+                    }
+                }
+                if let Some(target) = instr.jump_target(loc) {
+                    if target < 0 || target >= program.len() as i64 {
+                        errors.push(error(format!("jump target {} outside the program in '{}'", target, instr)));
+                    }
+                }
+            }
+            errors
+        }
+
+        // Palabras de memoria de datos que usa un programa del compilador:
+        // variables y constantes en d(5) y temporales en -d(6)
+        pub fn data_size(program: &[TmInstruction]) -> u32 {
+            let mut globals = 0;
+            let mut temps = 0;
+            for instr in program {
+                match instr {
+                    TmInstruction::Rm { op: "LD" | "ST", d, s: 5, .. } => globals = globals.max(d + 1),
+                    TmInstruction::Rm { op: "LD" | "ST", d, s: 6, .. } => temps = temps.max(1 - d),
+                    _ => {}
+                }
+            }
+            (globals + temps) as u32
+        }
+
+        // Objeto binario (.tmo), con todos los numeros en little endian:
+        //   encabezado  "TMO\0", version u16, banderas u16, numero de
+        //               instrucciones u32, memoria de datos u32
+        //   codigo      8 bytes por instruccion: operacion u8, r u8, s u8,
+        //               t u8, d i32. La operacion es su indice en RO_OPS
+        //               seguido de RM_OPS; el campo que la operacion no usa
+        //               (d en RO, t en RM) va en 0.
+        //   lineas      con la bandera TMO_SOURCE_MAP, la linea del programa
+        //               fuente de cada instruccion (u32, 0 si no tiene)
+        pub const TMO_MAGIC: &[u8; 4] = b"TMO\0";
+        pub const TMO_VERSION: u16 = 1;
+        pub const TMO_SOURCE_MAP: u16 = 1;
+
+        #[derive(Debug, Clone, PartialEq)]
+        pub struct TmObject {
+            pub code: Vec<TmInstruction>,
+            pub data_size: u32,
+            pub source_map: Option<Vec<u32>>,
+        }
+
+        fn opcode(op: &str) -> u8 {
+            RO_OPS.iter().chain(RM_OPS.iter()).position(|o| *o == op).unwrap() as u8
+        }
+
+        struct Reader<'a> {
+            bytes: &'a [u8],
+            pos: usize,
+        }
+
+        impl<'a> Reader<'a> {
+            fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+                if self.bytes.len() - self.pos < n {
+                    return Err(String::from("truncated TM object file"));
+                }
+                self.pos += n;
+                Ok(&self.bytes[self.pos - n..self.pos])
+            }
+
+            fn u16(&mut self) -> Result<u16, String> {
+                let b = self.take(2)?;
+                Ok(u16::from_le_bytes([b[0], b[1]]))
+            }
+
+            fn u32(&mut self) -> Result<u32, String> {
+                let b = self.take(4)?;
+                Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            }
+        }
+
+        impl TmObject {
+            // El programa debe estar validado: los registros caben en un byte
+            // y los desplazamientos en 32 bits
+            pub fn to_bytes(&self) -> Vec<u8> {
+                let mut bytes = Vec::new();
+                bytes.extend_from_slice(TMO_MAGIC);
+                bytes.extend_from_slice(&TMO_VERSION.to_le_bytes());
+                let flags = if self.source_map.is_some() { TMO_SOURCE_MAP } else { 0 };
+                bytes.extend_from_slice(&flags.to_le_bytes());
+                bytes.extend_from_slice(&(self.code.len() as u32).to_le_bytes());
+                bytes.extend_from_slice(&self.data_size.to_le_bytes());
+                for instr in &self.code {
+                    let (r, s, t, d) = match instr {
+                        TmInstruction::Ro { r, s, t, .. } => (*r, *s, *t, 0),
+                        TmInstruction::Rm { r, d, s, .. } => (*r, *s, 0, *d),
+                    };
+                    bytes.extend_from_slice(&[opcode(instr.op()), r as u8, s as u8, t as u8]);
+                    bytes.extend_from_slice(&(d as i32).to_le_bytes());
+                }
+                if let Some(lines) = &self.source_map {
+                    for line in lines {
+                        bytes.extend_from_slice(&line.to_le_bytes());
+                    }
+                }
+                bytes
+            }
+
+            pub fn from_bytes(bytes: &[u8]) -> Result<TmObject, String> {
+                let mut reader = Reader { bytes, pos: 0 };
+                if reader.take(4).ok() != Some(&TMO_MAGIC[..]) {
+                    return Err(String::from("not a TM object file"));
+                }
+                let version = reader.u16()?;
+                if version != TMO_VERSION {
+                    return Err(format!("unsupported TM object version {}", version));
+                }
+                let flags = reader.u16()?;
+                if flags & !TMO_SOURCE_MAP != 0 {
+                    return Err(format!("unknown TM object flags {:#x}", flags));
+                }
+                let count = reader.u32()?;
+                let data_size = reader.u32()?;
+                let mut code = Vec::new();
+                for loc in 0..count {
+                    let b = reader.take(8)?;
+                    let (r, s, t) = (b[1].into(), b[2].into(), b[3].into());
+                    let d = i32::from_le_bytes([b[4], b[5], b[6], b[7]]).into();
+                    let index = usize::from(b[0]);
+                    let instr = if index < RO_OPS.len() {
+                        TmInstruction::Ro { op: RO_OPS[index], r, s, t }
+                    } else if index < RO_OPS.len() + RM_OPS.len() {
+                        TmInstruction::Rm { op: RM_OPS[index - RO_OPS.len()], r, d, s }
+                    } else {
+                        return Err(format!("unknown opcode {}", index));
+                    };
+                    let unused = match instr {
+                        TmInstruction::Ro { .. } => d,
+                        TmInstruction::Rm { .. } => t,
+                    };
+                    if unused != 0 {
+                        return Err(format!("instruction {} has a nonzero unused operand", loc));
+                    }
+                    code.push(instr);
+                }
+                let source_map = if flags & TMO_SOURCE_MAP != 0 {
+                    Some((0..count).map(|_| reader.u32()).collect::<Result<Vec<u32>, String>>()?)
+                } else {
+                    None
+                };
+                if reader.pos != bytes.len() {
+                    return Err(String::from("unexpected data after the end of the TM object"));
+                }
+                Ok(TmObject { code, data_size, source_map })
+            }
+
+            // Texto del programa; la linea del programa fuente va como
+            // comentario antes de la primera instruccion que le corresponde
+            pub fn to_text(&self) -> String {
+                let mut out = format!("; data memory: {} words\n", self.data_size);
+                let mut last_line = 0;
+                for (loc, instr) in self.code.iter().enumerate() {
+                    if let Some(lines) = &self.source_map {
+                        if lines[loc] != 0 && lines[loc] != last_line {
+                            out.push_str(&format!("; line {}\n", lines[loc]));
+                        }
+                        last_line = lines[loc];
+                    }
+                    out.push_str(&format!("{}: {}\n", loc, instr));
+                }
+                out
+            }
+        }
+
+        // Simulador para las pruebas. Registros y memoria guardan f64, que
+        // representa exactamente los enteros de 32 bits. Devuelve lo que
+        // escriben OUT y FOUT, un valor por linea.
+        #[cfg(test)]
+        pub fn run(program: &[TmInstruction], input: &str) -> String {
+            let mut input = input.split_whitespace();
+            let mut reg = [0.0f64; NUM_REGS as usize];
+            let mut dmem = vec![0.0f64; 1024];
+            dmem[0] = 1023.0;
+            let mut out = String::new();
+            for _ in 0..1_000_000 {
+                let pc = reg[PC as usize] as usize;
+                reg[PC as usize] += 1.0;
+                match program[pc] {
+                    TmInstruction::Ro { op, r, s, t } => {
+                        let (r, a, b) = (r as usize, reg[s as usize], reg[t as usize]);
+                        reg[r] = match op {
+                            "HALT" => return out,
+                            "IN" | "FIN" => input.next().and_then(|word| word.parse().ok()).unwrap_or(0.0),
+                            "OUT" => {
+                                out.push_str(&format!("{}\n", reg[r] as i64));
+                                reg[r]
+                            }
+                            "FOUT" => {
+                                out.push_str(&format!("{}\n", crate::common::format_g(reg[r])));
+                                reg[r]
+                            }
+                            "ADD" | "FADD" => a + b,
+                            "SUB" | "FSUB" => a - b,
+                            "MUL" | "FMUL" => a * b,
+                            "DIV" | "FDIV" if b == 0.0 => panic!("division by zero at {}", pc),
+                            "DIV" => (a / b).trunc(),
+                            "FDIV" => a / b,
+                            "FTOI" => a.trunc(),
+                            _ => a,
+                        };
+                    }
+                    TmInstruction::Rm { op, r, d, s } => {
+                        let r = r as usize;
+                        let address = d + reg[s as usize] as i64;
+                        let taken = match op {
+                            "LD" => {
+                                reg[r] = dmem[address as usize];
+                                false
+                            }
+                            "ST" => {
+                                dmem[address as usize] = reg[r];
+                                false
+                            }
+                            "LDA" => {
+                                reg[r] = address as f64;
+                                false
+                            }
+                            "LDC" => {
+                                reg[r] = d as f64;
+                                false
+                            }
+                            "JLT" => reg[r] < 0.0,
+                            "JLE" => reg[r] <= 0.0,
+                            "JGT" => reg[r] > 0.0,
+                            "JGE" => reg[r] >= 0.0,
+                            "JEQ" => reg[r] == 0.0,
+                            _ => reg[r] != 0.0,
+                        };
+                        if taken {
+                            reg[PC as usize] = address as f64;
+                        }
+                    }
+                }
+            }
+            panic!("the TM program did not halt")
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use super::*;
+            use crate::common::samples;
+            use crate::tests::code_gen;
+
+            #[test]
+            fn samples_round_trip_through_text() {
+                for sample in samples() {
+                    let sample = sample.path.to_str().unwrap();
+                    for &runtime_checks in [false, true].iter() {
+                        let mut code_gen = code_gen(sample, true, runtime_checks);
+                        code_gen.peephole();
+                        let code = code_gen.to_object().code;
+                        assert!(validate(&code).is_empty(), "{}: {:?}", sample, validate(&code));
+                        // el texto del generador, con comentarios
+                        assert_eq!(parse_program(&code_gen.to_text()).unwrap(), code, "{}", sample);
+                        let text = program_text(&code);
+                        let parsed = parse_program(&text).unwrap();
+                        assert_eq!(parsed, code, "{}", sample);
+                        assert_eq!(program_text(&parsed), text, "{}", sample);
+                    }
+                }
+            }
+
+            #[test]
+            fn parse_rejects_malformed_instructions() {
+                let cases = [
+                    ("0: LDC 0,1(0)\n1: ADD 1,2\n", 2, "ADD expects operands r,s,t"),
+                    ("0: LD 0,1\n", 1, "LD expects operands r,d(s)"),
+                    ("0: MOV 0,0,0\n", 1, "unknown instruction 'MOV'"),
+                    ("; comentario\nx: HALT 0,0,0\n", 2, "bad address 'x'"),
+                    ("0: OUT a,0,0\n", 1, "expected a number, found 'a'"),
+                    ("HALT 0,0,0\n", 1, "expected 'N: instruction'"),
+                ];
+                for (text, line, message) in cases.iter() {
+                    let error = parse_program(text).unwrap_err();
+                    assert_eq!((error.line, error.message.as_str()), (*line, *message), "{}", text);
+                }
+            }
+
+            #[test]
+            fn parse_fills_gaps_with_halt() {
+                let program = parse_program("0: LDC 0,1(0)\n2: OUT 0,0,0\n").unwrap();
+                assert_eq!(program[1], TmInstruction::Ro { op: "HALT", r: 0, s: 0, t: 0 });
+                assert_eq!(program.len(), 3);
+            }
+
+            #[test]
+            fn validate_rejects_bad_programs() {
+                let program = parse_program("0: LDC 0,1(0)\n1: JEQ 0,5(7)\n2: HALT 0,0,0\n").unwrap();
+                let errors = validate(&program);
+                assert_eq!(errors.len(), 1);
+                assert_eq!(errors[0].line, 1);
+                assert_eq!(errors[0].message, "jump target 7 outside the program in 'JEQ 0,5(7)'");
+
+                let program = parse_program("0: LDA 7,-2(7)\n1: ADD 8,0,0\n2: LDC 0,4294967296(0)\n").unwrap();
+                let messages: Vec<String> = validate(&program).iter().map(|e| e.to_string()).collect();
+                assert_eq!(
+                    messages,
+                    [
+                        "0: jump target -1 outside the program in 'LDA 7,-2(7)'",
+                        "1: register 8 out of range in 'ADD 8,0,0'",
+                        "2: displacement 4294967296 out of range in 'LDC 0,4294967296(0)'",
+                    ]
+                );
+            }
+
+            #[test]
+            fn objects_round_trip_through_bytes() {
+                for sample in samples() {
+                    let sample = sample.path.to_str().unwrap();
+                    let mut code_gen = code_gen(sample, true, false);
+                    code_gen.peephole();
+                    let object = code_gen.to_object();
+                    assert_eq!(TmObject::from_bytes(&object.to_bytes()), Ok(object.clone()), "{}", sample);
+                    let object = TmObject { source_map: None, ..object };
+                    let bytes = object.to_bytes();
+                    assert_eq!(bytes.len(), 16 + 8 * object.code.len());
+                    assert_eq!(TmObject::from_bytes(&bytes), Ok(object), "{}", sample);
+                }
+            }
+
+            #[test]
+            fn from_bytes_rejects_bad_objects() {
+                let object = TmObject {
+                    code: parse_program("0: LDC 0,-7(0)\n1: OUT 0,0,0\n2: HALT 0,0,0\n").unwrap(),
+                    data_size: 0,
+                    source_map: Some(vec![1, 1, 0]),
+                };
+                let bytes = object.to_bytes();
+                let error = |bytes: &[u8]| TmObject::from_bytes(bytes).unwrap_err();
+
+                let mut wrong_magic = bytes.clone();
+                wrong_magic[0] = b'X';
+                assert_eq!(error(&wrong_magic), "not a TM object file");
+                assert_eq!(error(b"TM"), "not a TM object file");
+                for len in 4..bytes.len() {
+                    assert_eq!(error(&bytes[..len]), "truncated TM object file", "{} bytes", len);
+                }
+                let mut version = bytes.clone();
+                version[4] = 9;
+                assert_eq!(error(&version), "unsupported TM object version 9");
+                let mut opcode = bytes.clone();
+                opcode[16] = 200;
+                assert_eq!(error(&opcode), "unknown opcode 200");
+                let mut flags = bytes.clone();
+                flags[6] = 3;
+                assert_eq!(error(&flags), "unknown TM object flags 0x3");
+                // LDC no usa t y OUT no usa d
+                let mut unused_t = bytes.clone();
+                unused_t[16 + 3] = 1;
+                assert_eq!(error(&unused_t), "instruction 0 has a nonzero unused operand");
+                for byte in 4..8 {
+                    let mut unused_d = bytes.clone();
+                    unused_d[24 + byte] = 1;
+                    assert_eq!(error(&unused_d), "instruction 1 has a nonzero unused operand");
+                }
+                let mut extra = bytes;
+                extra.push(0);
+                assert_eq!(error(&extra), "unexpected data after the end of the TM object");
+            }
+        }
+    }
+
+    pub mod codegen {
+        use super::ir::{Instr, IrProgram, Op, Operand};
+        use super::regalloc;
+        use super::tm::{self, TmInstruction, TmObject};
+        use super::TinyType;
+
+        // Dialecto de TM con punto flotante:
+        // los registros y la memoria guardan enteros o flotantes y las
+        // instrucciones RO siguientes operan sobre flotantes:
+        //   FADD r,s,t   reg[r] = reg[s] + reg[t]
+        //   FSUB r,s,t   reg[r] = reg[s] - reg[t]
+        //   FMUL r,s,t   reg[r] = reg[s] * reg[t]
+        //   FDIV r,s,t   reg[r] = reg[s] / reg[t]
+        //   ITOF r,s,0   reg[r] = reg[s] convertido a flotante
+        //   FTOI r,s,0   reg[r] = reg[s] truncado a entero
+        //   FIN  r,0,0   lee un flotante en reg[r]
+        //   FOUT r,0,0   escribe el flotante de reg[r]
+        // LDC solo carga enteros, asi que una constante como 3.25 se calcula
+        // al inicio del programa como 325 / 100 usando ITOF y FDIV y se guarda
+        // despues de las variables. Las comparaciones de flotantes usan FSUB
+        // y los saltos normales (JLT, JEQ, ...) sobre el resultado.
+        //
+        // Las variables estan en loc(5). El temporal tN del codigo intermedio
+        // esta en el registro que le asigno regalloc o, si no tiene, en -N(6),
+        // al final de la memoria.
+        //
+        // Con las verificaciones en tiempo de ejecucion activas, una division
+        // entre cero o un desbordamiento de enteros de 32 bits escribe el
+        // codigo de trampa y la linea del programa fuente y termina con HALT.
+        // El desbordamiento se revisa repitiendo la operacion en flotante
+        // antes de la operacion entera.
+        pub const TRAP_DIV_ZERO: i64 = 1;
+        pub const TRAP_OVERFLOW: i64 = 2;
+
+        // Codigo generado antes de asignar direcciones. Los saltos van a una
+        // etiqueta y se convierten en saltos relativos al pc al final.
+        #[derive(Debug, Clone, PartialEq)]
+        enum TmLine {
+            Comment(String),
+            // linea del programa fuente de las instrucciones que siguen
+            Source(u32),
+            Label(usize),
+            Instr(TmInstruction, String),
+            Jump { op: &'static str, r: i64, label: usize, comment: String },
+        }
+
+        impl TmLine {
+            fn is_code(&self) -> bool {
+                matches!(self, TmLine::Instr(..) | TmLine::Jump { .. })
+            }
+        }
+
+        pub struct CodeGenResult {
+            runtime_checks: bool,
+            lines: Vec<TmLine>,
+            // etiquetas del codigo intermedio seguidas de las que agrega el generador
+            labels: usize,
+            // constantes flotantes y su localidad de datos
+            float_consts: Vec<(f64, i64)>,
+            // registro de cada temporal, None si esta en memoria
+            registers: Vec<Option<i64>>,
+            // localidad -d(6) de cada temporal en memoria
+            slots: Vec<Option<i64>>,
+        }
+
+        impl CodeGenResult {
+            pub fn new() -> CodeGenResult {
+                CodeGenResult {
+                    runtime_checks: false,
+                    lines: Vec::new(),
+                    labels: 0,
+                    float_consts: Vec::new(),
+                    registers: Vec::new(),
+                    slots: Vec::new(),
+                }
+            }
+
+            pub fn set_registers(&mut self, registers: Vec<Option<i64>>){
+                self.registers = registers;
+            }
+
+            pub fn set_runtime_checks(&mut self, enabled: bool){
+                self.runtime_checks = enabled;
+            }
+
+            // Genera el codigo TM sin optimizar; las direcciones se asignan en to_text
+            pub fn generate(&mut self, program: &IrProgram){
+                self.labels = program.labels;
+                self.slots = regalloc::spill_slots(program, &self.registers);
+                self.emit_comment("TINY Compilation to TM Code");
+                self.emit_comment("Standard prelude:");
+                self.emit_rm("LD", 6, 0, 0, "load maxaddress from location 0");
+                self.emit_rm("ST", 0, 0, 0, "clear location 0");
+                self.emit_float_consts(program);
+                self.emit_comment("End of standard prelude.");
+                for quad in &program.code {
+                    self.lines.push(TmLine::Source(quad.line));
+                    self.emit_comment(&program.instr_text(&quad.instr));
+                    self.code_gen_instr(program, &quad.instr, quad.line);
+                }
+                self.lines.push(TmLine::Source(0));
+                self.emit_comment("End of execution.");
+                self.emit_ro("HALT", 0, 0, 0, "");
+            }
+
+            fn label_addresses(&self) -> Vec<i64> {
+                let mut addresses = vec![0; self.labels];
+                let mut loc = 0;
+                for line in &self.lines {
+                    match line {
+                        TmLine::Label(label) => addresses[*label] = loc,
+                        line if line.is_code() => loc += 1,
+                        _ => {}
+                    }
+                }
+                addresses
+            }
+
+            // Instruccion de la linea en la direccion `loc`, con los saltos
+            // ya convertidos en saltos relativos al pc
+            fn resolve<'a>(line: &'a TmLine, loc: i64, addresses: &[i64]) -> Option<(TmInstruction, &'a str)> {
+                match line {
+                    TmLine::Instr(instr, comment) => Some((instr.clone(), comment)),
+                    TmLine::Jump { op, r, label, comment } => {
+                        let d = addresses[*label] - (loc + 1);
+                        Some((TmInstruction::Rm { op, r: *r, d, s: 7 }, comment))
+                    }
+                    _ => None,
+                }
+            }
+
+            // Asigna direcciones a las instrucciones y regresa el codigo TM
+            pub fn to_text(&self) -> String {
+                let mut out = String::new();
+                let addresses = self.label_addresses();
+                let mut loc: i64 = 0;
+                for line in &self.lines {
+                    if let TmLine::Comment(comment) = line {
+                        out.push_str(&format!("; {}\n", comment));
+                    }
+                    if let Some((instr, comment)) = CodeGenResult::resolve(line, loc, &addresses) {
+                        out.push_str(&format!("{}: {}\n", loc, instr));
+                        out.push_str(&format!("; {}\n", comment));
+                        loc += 1;
+                    }
+                }
+                out
+            }
+
+            // Instrucciones en orden de direccion con su comentario y la linea
+            // del programa fuente que las genero (0 en el preludio y al final)
+            fn instructions(&self) -> Vec<(TmInstruction, &str, u32)> {
+                let addresses = self.label_addresses();
+                let mut instructions = Vec::new();
+                let mut source_line = 0;
+                for line in &self.lines {
+                    if let TmLine::Source(line) = line {
+                        source_line = *line;
+                    }
+                    if let Some((instr, comment)) = CodeGenResult::resolve(line, instructions.len() as i64, &addresses) {
+                        instructions.push((instr, comment, source_line));
+                    }
+                }
+                instructions
+            }
+
+            // Objeto binario con la linea del programa fuente de cada instruccion
+            pub fn to_object(&self) -> TmObject {
+                let (code, source_map): (Vec<TmInstruction>, Vec<u32>) =
+                    self.instructions().into_iter().map(|(instr, _, line)| (instr, line)).unzip();
+                TmObject {
+                    data_size: tm::data_size(&code),
+                    code,
+                    source_map: Some(source_map),
+                }
+            }
+
+            // Listado: cada linea del programa fuente seguida de las
+            // instrucciones que se generaron para ella
+            pub fn listing(&self, source: &[String]) -> String {
+                let mut out = String::new();
+                let mut printed = 0;
+                for (loc, (instr, comment, line)) in self.instructions().iter().enumerate() {
+                    // el codigo del final va despues de todo el programa fuente
+                    let line = if *line == 0 && printed > 0 { source.len() } else { *line as usize };
+                    while printed < line.min(source.len()) {
+                        out.push_str(&format!("{:>4}: {}\n", printed + 1, source[printed]));
+                        printed += 1;
+                    }
+                    let code = format!("{:>10}:  {:<18}{}", loc, instr.to_string(), comment);
+                    out.push_str(code.trim_end());
+                    out.push('\n');
+                }
+                while printed < source.len() {
+                    out.push_str(&format!("{:>4}: {}\n", printed + 1, source[printed]));
+                    printed += 1;
+                }
+                out
+            }
+
+            fn emit_comment(&mut self, comment: &str){
+                self.lines.push(TmLine::Comment(comment.to_string()));
+            }
+
+            fn emit_rm(&mut self, op: &'static str, r: i64, d: i64, s: i64, comment: &str){
+                self.lines.push(TmLine::Instr(TmInstruction::Rm { op, r, d, s }, comment.to_string()));
+            }
+
+            fn emit_ro(&mut self, op: &'static str, r: i64, s: i64, t: i64, comment: &str){
+                self.lines.push(TmLine::Instr(TmInstruction::Ro { op, r, s, t }, comment.to_string()));
+            }
+
+            fn new_label(&mut self) -> usize {
+                self.labels += 1;
+                self.labels - 1
+            }
+
+            fn emit_label(&mut self, label: usize){
+                self.lines.push(TmLine::Label(label));
+            }
+
+            fn emit_jump(&mut self, op: &'static str, r: i64, label: usize, comment: &str){
+                self.lines.push(TmLine::Jump { op, r, label, comment: comment.to_string() });
+            }
+
+            // escribe el codigo de trampa y la linea, y detiene la maquina
+            fn emit_trap(&mut self, code: i64, line: u32){
+                self.emit_rm("LDC", 0, code, 0, "trap: load code");
+                self.emit_ro("OUT", 0, 0, 0, "trap: write code");
+                self.emit_rm("LDC", 0, line.into(), 0, "trap: load line");
+                self.emit_ro("OUT", 0, 0, 0, "trap: write line");
+                self.emit_ro("HALT", 0, 0, 0, "trap: halt");
+            }
+
+            fn emit_div_check(&mut self, right: i64, line: u32){
+                let ok = self.new_label();
+                self.emit_jump("JNE", right, ok, "check: divisor is not zero");
+                self.emit_trap(TRAP_DIV_ZERO, line);
+                self.emit_label(ok);
+            }
+
+            // usa ac y ac1, hay que volver a cargar los operandos que estaban ahi
+            fn emit_overflow_check(&mut self, op: &'static str, left: i64, right: i64, line: u32){
+                let trap = self.new_label();
+                let ok = self.new_label();
+                self.emit_ro("ITOF", 1, left, 0, "check: left to float");
+                self.emit_ro("ITOF", 0, right, 0, "check: right to float");
+                self.emit_ro(op, 0, 1, 0, "check: exact result");
+                self.emit_rm("LDC", 1, i32::MAX.into(), 0, "check: load max int");
+                self.emit_ro("ITOF", 1, 1, 0, "check: max to float");
+                self.emit_ro("FSUB", 1, 0, 1, "check: compare with max");
+                self.emit_jump("JGT", 1, trap, "check: overflow");
+                self.emit_rm("LDC", 1, i32::MIN.into(), 0, "check: load min int");
+                self.emit_ro("ITOF", 1, 1, 0, "check: min to float");
+                self.emit_ro("FSUB", 1, 0, 1, "check: compare with min");
+                self.emit_jump("JGE", 1, ok, "check: no overflow");
+                self.emit_label(trap);
+                self.emit_trap(TRAP_OVERFLOW, line);
+                self.emit_label(ok);
+            }
+
+            // Calcula cada constante flotante como mantisa / 10^k y la guarda
+            // despues de las variables. Si la mantisa no cabe en un entero de
+            // 32 bits se arma por piezas de 9 digitos: m = m * 10^9 + pieza.
+            fn emit_float_consts(&mut self, program: &IrProgram){
+                let mut loc = i64::from(program.mem_size);
+                for quad in &program.code {
+                    for value in float_operands(&quad.instr) {
+                        if self.float_consts.iter().any(|(v, _)| v.to_bits() == value.to_bits()) {
+                            continue;
+                        }
+                        let text = value.abs().to_string();
+                        let (int_part, frac_part) = match text.find('.') {
+                            Some(dot) => (&text[..dot], &text[dot + 1..]),
+                            None => (&text[..], ""),
+                        };
+                        let mantissa = format!("{}{}", int_part, frac_part);
+                        let mantissa = match mantissa.trim_start_matches('0') {
+                            "" => "0",
+                            digits => digits,
+                        };
+                        let pieces: Vec<&str> = if mantissa.parse::<i32>().is_ok() {
+                            vec![mantissa]
+                        } else {
+                            let first = match mantissa.len() % 9 {
+                                0 => 9,
+                                len => len,
+                            };
+                            let mut pieces = vec![&mantissa[..first]];
+                            pieces.extend(mantissa.as_bytes()[first..].chunks(9).map(|piece| {
+                                std::str::from_utf8(piece).unwrap()
+                            }));
+                            pieces
+                        };
+                        let add = if value < 0.0 { "FSUB" } else { "FADD" };
+                        let sign = if value < 0.0 { -1 } else { 1 };
+                        let piece: i64 = pieces[0].parse().unwrap();
+                        self.emit_rm("LDC", 0, sign * piece, 0, "const: load mantissa");
+                        self.emit_ro("ITOF", 0, 0, 0, "const: to float");
+                        for piece in &pieces[1..] {
+                            self.emit_rm("LDC", 1, 1_000_000_000, 0, "const: load shift");
+                            self.emit_ro("ITOF", 1, 1, 0, "const: shift to float");
+                            self.emit_ro("FMUL", 0, 0, 1, "const: mantissa * shift");
+                            self.emit_rm("LDC", 1, piece.parse().unwrap(), 0, "const: load piece");
+                            self.emit_ro("ITOF", 1, 1, 0, "const: piece to float");
+                            self.emit_ro(add, 0, 0, 1, "const: mantissa + piece");
+                        }
+                        let mut digits = frac_part.len();
+                        while digits > 0 {
+                            let step = digits.min(9);
+                            self.emit_rm("LDC", 1, 10i64.pow(step as u32), 0, "const: load scale");
+                            self.emit_ro("ITOF", 1, 1, 0, "const: scale to float");
+                            self.emit_ro("FDIV", 0, 0, 1, "const: mantissa / scale");
+                            digits -= step;
+                        }
+                        self.emit_rm("ST", 0, loc, 5, &format!("const: store {:?}", value));
+                        self.float_consts.push((value, loc));
+                        loc += 1;
+                    }
+                }
+            }
+
+            fn emit_load(&mut self, program: &IrProgram, r: i64, operand: &Operand){
+                match operand {
+                    Operand::Temp(t) => self.emit_rm("LD", r, self.slots[*t].unwrap(), 6, "load temp"),
+                    Operand::Var(v) => {
+                        let loc = program.vars[*v].location;
+                        self.emit_rm("LD", r, loc.into(), 5, "load id value");
+                    }
+                    Operand::Int(n) => self.emit_rm("LDC", r, (*n).into(), 0, "load const"),
+                    Operand::Bool(b) => self.emit_rm("LDC", r, (*b).into(), 0, "load const"),
+                    Operand::Float(f) => {
+                        let loc = self
+                            .float_consts
+                            .iter()
+                            .find(|(v, _)| v.to_bits() == f.to_bits())
+                            .map_or(0, |(_, loc)| *loc);
+                        self.emit_rm("LD", r, loc, 5, "load float const");
+                    }
+                }
+            }
+
+            fn temp_reg(&self, operand: &Operand) -> Option<i64> {
+                match operand {
+                    Operand::Temp(t) => self.registers.get(*t).copied().flatten(),
+                    _ => None,
+                }
+            }
+
+            // registro con el valor del operando; si no tiene uno se carga en `scratch`
+            fn emit_operand(&mut self, program: &IrProgram, operand: &Operand, scratch: i64) -> i64 {
+                match self.temp_reg(operand) {
+                    Some(reg) => reg,
+                    None => {
+                        self.emit_load(program, scratch, operand);
+                        scratch
+                    }
+                }
+            }
+
+            // registro donde se calcula el valor de `dest`
+            fn dest_reg(&self, dest: &Operand) -> i64 {
+                self.temp_reg(dest).unwrap_or(0)
+            }
+
+            // guarda el valor de `reg` si `dest` no vive en un registro
+            fn emit_store(&mut self, program: &IrProgram, reg: i64, dest: &Operand){
+                if self.temp_reg(dest).is_some() {
+                    return;
+                }
+                match dest {
+                    Operand::Temp(t) => self.emit_rm("ST", reg, self.slots[*t].unwrap(), 6, "store temp"),
+                    Operand::Var(v) => {
+                        let loc = program.vars[*v].location;
+                        self.emit_rm("ST", reg, loc.into(), 5, "store value");
+                    }
+                    _ => {}
+                }
+            }
+
+            fn code_gen_instr(&mut self, program: &IrProgram, instr: &Instr, line: u32){
+                match instr {
+                    Instr::Copy { dest, src } => match (self.temp_reg(dest), self.temp_reg(src)) {
+                        (Some(d), Some(r)) => self.emit_rm("LDA", d, 0, r, "move temp"),
+                        (Some(d), None) => self.emit_load(program, d, src),
+                        (None, _) => {
+                            let r = self.emit_operand(program, src, 0);
+                            self.emit_store(program, r, dest);
+                        }
+                    },
+                    Instr::Binary { op, ty, dest, left, right } => {
+                        let mut l = self.emit_operand(program, left, 1);
+                        let mut r = self.emit_operand(program, right, 0);
+                        let d = self.dest_reg(dest);
+                        let float = *ty == TinyType::Float;
+                        if op.is_relational() {
+                            let true_case = self.new_label();
+                            let end = self.new_label();
+                            self.emit_ro(if float { "FSUB" } else { "SUB" }, 0, l, r, &format!("op {}", op.symbol()));
+                            self.emit_jump(jump_op(*op), 0, true_case, "br if true");
+                            self.emit_rm("LDC", d, 0, 0, "false case");
+                            self.emit_jump("LDA", 7, end, "unconditional jmp");
+                            self.emit_label(true_case);
+                            self.emit_rm("LDC", d, 1, 0, "true case");
+                            self.emit_label(end);
+                        } else {
+                            // la division flotante entre cero tambien es una trampa
+                            if self.runtime_checks && *op == Op::Div {
+                                self.emit_div_check(r, line);
+                            }
+                            if self.runtime_checks && !float {
+                                self.emit_overflow_check(float_op(*op), l, r, line);
+                                l = self.emit_operand(program, left, 1);
+                                r = self.emit_operand(program, right, 0);
+                            }
+                            let name = if float { float_op(*op) } else { int_op(*op) };
+                            self.emit_ro(name, d, l, r, &format!("op: {}", op.symbol()));
+                        }
+                        self.emit_store(program, d, dest);
+                    }
+                    Instr::Cast { to, dest, src } => {
+                        let r = self.emit_operand(program, src, 0);
+                        let d = self.dest_reg(dest);
+                        match (program.operand_type(src), to) {
+                            (TinyType::Integer, TinyType::Float) => {
+                                self.emit_ro("ITOF", d, r, 0, "cast: int to float");
+                            }
+                            (TinyType::Float, TinyType::Integer) => {
+                                self.emit_ro("FTOI", d, r, 0, "cast: float to int");
+                            }
+                            _ if d != r => self.emit_rm("LDA", d, 0, r, "move temp"),
+                            _ => {}
+                        }
+                        self.emit_store(program, d, dest);
+                    }
+                    Instr::Label(label) => {
+                        self.emit_label(*label);
+                    }
+                    Instr::Goto(label) => {
+                        self.emit_jump("LDA", 7, *label, &format!("jmp to L{}", label));
+                    }
+                    Instr::CondGoto { op, ty, left, right, target } => {
+                        let l = self.emit_operand(program, left, 1);
+                        let r = self.emit_operand(program, right, 0);
+                        let sub = if *ty == TinyType::Float { "FSUB" } else { "SUB" };
+                        self.emit_ro(sub, 0, l, r, "rel: compare");
+                        self.emit_jump(jump_op(*op), 0, *target, &format!("br to L{}", target));
+                    }
+                    Instr::Read { ty, dest } => {
+                        let d = self.dest_reg(dest);
+                        if *ty == TinyType::Float {
+                            self.emit_ro("FIN", d, 0, 0, "read float value");
+                        } else {
+                            self.emit_ro("IN", d, 0, 0, "read value");
+                        }
+                        self.emit_store(program, d, dest);
+                    }
+                    Instr::Write { ty, src } => {
+                        let r = self.emit_operand(program, src, 0);
+                        if *ty == TinyType::Float {
+                            self.emit_ro("FOUT", r, 0, 0, "write float ac");
+                        } else {
+                            self.emit_ro("OUT", r, 0, 0, "write ac");
+                        }
+                    }
+                }
+            }
+
+            // Siguiente instruccion despues de `index`; None si antes hay una
+            // etiqueta, porque ahi puede llegar un salto
+            fn next_code(&self, index: usize) -> Option<usize> {
+                for next in index + 1..self.lines.len() {
+                    match self.lines[next] {
+                        TmLine::Label(_) => return None,
+                        TmLine::Comment(_) | TmLine::Source(_) => {}
+                        _ => return Some(next),
+                    }
+                }
+                None
+            }
+
+            // Un salto a la instruccion siguiente no hace nada
+            fn jumps_to_next(&self, index: usize) -> bool {
+                let label = match &self.lines[index] {
+                    TmLine::Jump { label, .. } => *label,
+                    TmLine::Instr(TmInstruction::Rm { op, r, d: 0, s: 7 }, _) => {
+                        return op.starts_with('J') || (*op == "LDA" && *r == 7);
+                    }
+                    _ => return false,
+                };
+                self.lines[index + 1..]
+                    .iter()
+                    .take_while(|line| !line.is_code())
+                    .any(|line| *line == TmLine::Label(label))
+            }
+
+            // Un temporal que nunca se vuelve a cargar no necesita guardarse
+            fn is_dead_store(&self, index: usize) -> bool {
+                match &self.lines[index] {
+                    TmLine::Instr(TmInstruction::Rm { op: "ST", d, s: 6, .. }, _) => !self.lines.iter().any(|line| {
+                        matches!(line, TmLine::Instr(TmInstruction::Rm { op: "LD", d: ld, s: 6, .. }, _) if ld == d)
+                    }),
+                    _ => false,
+                }
+            }
+
+            // Quita ST seguido de LD de la misma localidad, LD seguido de ST
+            // a la misma localidad, saltos a la instruccion siguiente y
+            // temporales que no se leen
+            pub fn peephole(&mut self){
+                let mut changed = true;
+                while changed {
+                    changed = false;
+                    let mut index = 0;
+                    while index < self.lines.len() {
+                        let remove = if self.jumps_to_next(index) || self.is_dead_store(index) {
+                            Some(index)
+                        } else {
+                            match (&self.lines[index], self.next_code(index)) {
+                                (TmLine::Instr(TmInstruction::Rm { op, r, d, s }, _), Some(next)) => match &self.lines[next] {
+                                    TmLine::Instr(TmInstruction::Rm { op: next_op, r: nr, d: nd, s: ns }, _)
+                                        if (r, d, s) == (nr, nd, ns)
+                                            && ((*op == "ST" && *next_op == "LD") || (*op == "LD" && *next_op == "ST")) =>
+                                    {
+                                        Some(next)
+                                    }
+                                    _ => None,
+                                },
+                                _ => None,
+                            }
+                        };
+                        match remove {
+                            Some(line) => {
+                                self.lines.remove(line);
+                                changed = true;
+                            }
+                            None => index += 1,
+                        }
+                    }
+                }
+            }
+        }
+
+        fn float_operands(instr: &Instr) -> Vec<f64> {
+            let operands = match instr {
+                Instr::Copy { src, .. } | Instr::Cast { src, .. } | Instr::Write { src, .. } => vec![*src],
+                Instr::Binary { left, right, .. } | Instr::CondGoto { left, right, .. } => vec![*left, *right],
+                _ => vec![],
+            };
+            operands
+                .into_iter()
+                .filter_map(|operand| match operand {
+                    Operand::Float(value) => Some(value),
+                    _ => None,
+                })
+                .collect()
+        }
+
+        fn int_op(op: Op) -> &'static str {
+            match op {
+                Op::Add => "ADD",
+                Op::Sub => "SUB",
+                Op::Mul => "MUL",
+                _ => "DIV",
+            }
+        }
+
+        fn float_op(op: Op) -> &'static str {
+            match op {
+                Op::Add => "FADD",
+                Op::Sub => "FSUB",
+                Op::Mul => "FMUL",
+                _ => "FDIV",
+            }
+        }
+
+        // salto que se toma cuando `left - right` cumple la relacion
+        fn jump_op(op: Op) -> &'static str {
+            match op {
+                Op::Lt => "JLT",
+                Op::Le => "JLE",
+                Op::Gt => "JGT",
+                Op::Ge => "JGE",
+                Op::Eq => "JEQ",
+                _ => "JNE",
+            }
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use super::*;
+            use crate::common::terminating;
+            use crate::tests::code_gen;
+
+            // `L0` es una etiqueta; lo demas, una instruccion
+            fn program(lines: &[&str]) -> CodeGenResult {
+                let mut code_gen = CodeGenResult::new();
+                code_gen.labels = 1;
+                code_gen.lines = lines
+                    .iter()
+                    .map(|line| match *line {
+                        "L0" => TmLine::Label(0),
+                        line => TmLine::Instr(TmInstruction::parse(line).unwrap(), String::new()),
+                    })
+                    .collect();
+                code_gen
+            }
+
+            // Aplica peephole, revisa que quite solo la instruccion en la
+            // direccion `removed` y que el programa escriba lo mismo antes y despues
+            fn check_rule(mut code_gen: CodeGenResult, removed: Option<usize>, output: &str) {
+                let before = code_gen.to_object().code;
+                code_gen.peephole();
+                let after = code_gen.to_object().code;
+                let mut expected = before.clone();
+                if let Some(loc) = removed {
+                    expected.remove(loc);
+                }
+                assert_eq!(after, expected);
+                assert_eq!(tm::run(&before, ""), output);
+                assert_eq!(tm::run(&after, ""), output);
+            }
+
+            #[test]
+            fn load_after_store_is_removed() {
+                let code_gen = program(&["LDC 0,7(0)", "ST 0,1(5)", "LD 0,1(5)", "OUT 0,0,0", "HALT 0,0,0"]);
+                check_rule(code_gen, Some(2), "7\n");
+            }
+
+            #[test]
+            fn store_after_load_is_removed() {
+                let code_gen = program(&[
+                    "LDC 0,3(0)",
+                    "ST 0,2(5)",
+                    "LDC 0,0(0)",
+                    "LD 0,2(5)",
+                    "ST 0,2(5)",
+                    "OUT 0,0,0",
+                    "HALT 0,0,0",
+                ]);
+                check_rule(code_gen, Some(4), "3\n");
+            }
+
+            #[test]
+            fn jump_to_next_instruction_is_removed() {
+                let mut code_gen = program(&["LDC 0,0(0)", "L0", "OUT 0,0,0", "HALT 0,0,0"]);
+                let jump = TmLine::Jump { op: "JEQ", r: 0, label: 0, comment: String::new() };
+                code_gen.lines.insert(1, jump);
+                check_rule(code_gen, Some(1), "0\n");
+                let code_gen = program(&["LDC 0,4(0)", "LDA 7,0(7)", "OUT 0,0,0", "HALT 0,0,0"]);
+                check_rule(code_gen, Some(1), "4\n");
+            }
+
+            #[test]
+            fn temp_that_is_never_loaded_is_not_stored() {
+                let code_gen = program(&[
+                    "LD 6,0(0)",
+                    "LDC 0,5(0)",
+                    "ST 0,-1(6)",
+                    "ST 0,-2(6)",
+                    "LDC 0,0(0)",
+                    "LD 1,-2(6)",
+                    "OUT 1,0,0",
+                    "HALT 0,0,0",
+                ]);
+                check_rule(code_gen, Some(2), "5\n");
+            }
+
+            #[test]
+            fn load_after_a_label_is_kept() {
+                let code_gen = program(&["LDC 0,7(0)", "ST 0,1(5)", "L0", "LD 0,1(5)", "OUT 0,0,0", "HALT 0,0,0"]);
+                check_rule(code_gen, None, "7\n");
+            }
+
+            #[test]
+            fn peephole_preserves_sample_output() {
+                let mut removed = 0;
+                for (sample, expected) in terminating() {
+                    for &(regalloc, runtime_checks) in [(true, false), (false, false), (false, true)].iter() {
+                        let mut code_gen = code_gen(sample.path.to_str().unwrap(), regalloc, runtime_checks);
+                        let before = code_gen.to_object().code;
+                        code_gen.peephole();
+                        let after = code_gen.to_object().code;
+                        removed += before.len() - after.len();
+                        assert_eq!(tm::run(&before, &sample.input), expected, "{}", sample.name);
+                        assert_eq!(tm::run(&after, &sample.input), expected, "{}", sample.name);
+                    }
+                }
+                assert!(removed > 0);
+            }
+        }
+    }
+
+    // Traduce el arbol revisado a un programa C99. Todas las variables, aun
+    // las de un bloque, se declaran una vez al inicio de main con valor 0:
+    // en TM cada una tiene su propia localidad, que empieza en 0 y conserva
+    // su valor de una vuelta de un ciclo a la siguiente. read/write usan
+    // scanf/printf con un valor por linea.
+    pub mod c_backend {
+        use super::analyzer::SymbolTable;
+        use super::ir::Op;
+        use super::{StatementType, TinyType, TokenType, TreeNode};
+
+        // nombres que no pueden usarse tal cual en el programa C: palabras
+        // reservadas, macros de los encabezados que se incluyen y funciones
+        // que llama el programa
+        const RESERVED: &[&str] = &[
+            "auto", "break", "case", "char", "const", "continue", "default", "do", "double", "else", "enum",
+            "extern", "float", "for", "goto", "if", "inline", "int", "long", "register", "restrict", "return",
+            "short", "signed", "sizeof", "static", "struct", "switch", "typedef", "union", "unsigned", "void",
+            "volatile", "while", "bool", "true", "false", "BUFSIZ", "EOF", "FILENAME_MAX", "FOPEN_MAX",
+            "L_tmpnam", "NULL", "SEEK_CUR", "SEEK_END", "SEEK_SET", "TMP_MAX", "stderr", "stdin", "stdout",
+            "CHAR_BIT", "SCHAR_MIN", "SCHAR_MAX", "UCHAR_MAX", "CHAR_MIN", "CHAR_MAX", "MB_LEN_MAX", "SHRT_MIN",
+            "SHRT_MAX", "USHRT_MAX", "INT_MIN", "INT_MAX", "UINT_MAX", "LONG_MIN", "LONG_MAX", "ULONG_MAX",
+            "LLONG_MIN", "LLONG_MAX", "ULLONG_MAX", "EXIT_FAILURE", "EXIT_SUCCESS", "MB_CUR_MAX", "RAND_MAX",
+            "linux", "unix", "main", "printf", "scanf", "exit", "read_int", "read_float", "wrap_add", "wrap_sub",
+            "wrap_mul", "trap", "checked", "checked_add", "checked_sub", "checked_mul", "checked_div", "checked_fdiv",
+        ];
+
+        const PRELUDE: &str = "#include <stdbool.h>
+#include <stdio.h>
+
+static inline int read_int(void)
+{
+    int value = 0;
+    if (scanf(\"%d\", &value) != 1)
+        return 0;
+    return value;
+}
+
+static inline double read_float(void)
+{
+    double value = 0.0;
+    if (scanf(\"%lf\", &value) != 1)
+        return 0.0;
+    return value;
+}
+
+/* El desbordamiento da la vuelta como en TM; en int seria indefinido */
+static inline int wrap_add(int left, int right) { return (int)((unsigned)left + (unsigned)right); }
+static inline int wrap_sub(int left, int right) { return (int)((unsigned)left - (unsigned)right); }
+static inline int wrap_mul(int left, int right) { return (int)((unsigned)left * (unsigned)right); }
+";
+
+        // Con --runtime-checks las operaciones enteras y la division flotante
+        // escriben el codigo de trampa y la linea y terminan, igual que el codigo TM
+        const CHECKS: &str = "
+#include <limits.h>
+#include <stdlib.h>
+
+static inline void trap(int code, int line)
+{
+    printf(\"%d\\n%d\\n\", code, line);
+    exit(EXIT_FAILURE);
+}
+
+static inline int checked(long long value, int line)
+{
+    if (value > INT_MAX || value < INT_MIN)
+        trap(2, line);
+    return (int)value;
+}
+
+static inline int checked_add(int left, int right, int line) { return checked((long long)left + right, line); }
+static inline int checked_sub(int left, int right, int line) { return checked((long long)left - right, line); }
+static inline int checked_mul(int left, int right, int line) { return checked((long long)left * right, line); }
+
+static inline int checked_div(int left, int right, int line)
+{
+    if (right == 0)
+        trap(1, line);
+    return checked((long long)left / right, line);
+}
+
+static inline double checked_fdiv(double left, double right, int line)
+{
+    if (right == 0.0)
+        trap(1, line);
+    return left / right;
+}
+";
+
+        fn c_type(ty: TinyType) -> &'static str {
+            match ty {
+                TinyType::Float => "double",
+                TinyType::Boolean => "bool",
+                _ => "int",
+            }
+        }
+
+        struct CGen {
+            names: Vec<String>,
+            types: Vec<TinyType>,
+            runtime_checks: bool,
+            out: String,
+            indent: usize,
+        }
+
+        impl CGen {
+            fn line(&mut self, text: &str) {
+                if !text.is_empty() {
+                    for _ in 0..self.indent {
+                        self.out.push_str("    ");
+                    }
+                    self.out.push_str(text);
+                }
+                self.out.push('\n');
+            }
+
+            // una variable no declarada ya se reporto, se usa 0 en su lugar
+            fn var(&self, node: &TreeNode) -> String {
+                node.symbol.map_or(String::from("0"), |symbol| self.names[symbol].clone())
+            }
+
+            fn expr(&self, node: &TreeNode) -> String {
+                match node.statement_type {
+                    StatementType::Literal => {
+                        // los enteros se escriben en decimal: en C `010` seria octal
+                        let text = match node.val_type {
+                            TinyType::Integer => node.int_value().to_string(),
+                            _ => node.token.lexema.clone(),
+                        };
+                        if text == i32::MIN.to_string() {
+                            String::from("(-2147483647 - 1)")
+                        } else if text.starts_with('-') {
+                            format!("({})", text)
+                        } else {
+                            text
+                        }
+                    }
+                    StatementType::LiteralBoolExp => node.token.lexema.clone(),
+                    StatementType::Variable => self.var(node),
+                    StatementType::Arithmetic | StatementType::Relational => {
+                        let left = self.expr(&node.nodes[0]);
+                        let right = self.expr(&node.nodes[1]);
+                        let op = Op::from_token(&node.token.token).unwrap_or(Op::Add);
+                        if self.runtime_checks && op == Op::Div && node.val_type == TinyType::Float {
+                            format!("checked_fdiv({}, {}, {})", left, right, node.token.line)
+                        } else if op.is_relational() || node.val_type != TinyType::Integer {
+                            format!("({} {} {})", left, op.symbol(), right)
+                        } else if self.runtime_checks {
+                            let name = match op {
+                                Op::Add => "checked_add",
+                                Op::Sub => "checked_sub",
+                                Op::Mul => "checked_mul",
+                                _ => "checked_div",
+                            };
+                            format!("{}({}, {}, {})", name, left, right, node.token.line)
+                        } else if op != Op::Div {
+                            let name = match op {
+                                Op::Add => "wrap_add",
+                                Op::Sub => "wrap_sub",
+                                _ => "wrap_mul",
+                            };
+                            format!("{}({}, {})", name, left, right)
+                        } else {
+                            format!("({} {} {})", left, op.symbol(), right)
+                        }
+                    }
+                    StatementType::Cast => format!("(({})({}))", c_type(node.val_type), self.expr(&node.nodes[0])),
+                    StatementType::Not => format!("(!{})", self.expr(&node.nodes[0])),
+                    StatementType::BooleanExp => {
+                        let op = if node.token.token == TokenType::TK_OR { "||" } else { "&&" };
+                        format!("({} {} {})", self.expr(&node.nodes[0]), op, self.expr(&node.nodes[1]))
+                    }
+                    _ => String::from("0"),
+                }
+            }
+
+            // expresion sin los parentesis de afuera, para condiciones y asignaciones
+            fn bare(&self, node: &TreeNode) -> String {
+                let text = self.expr(node);
+                match node.statement_type {
+                    StatementType::Arithmetic | StatementType::Relational | StatementType::BooleanExp
+                        if text.starts_with('(') =>
+                    {
+                        String::from(&text[1..text.len() - 1])
+                    }
+                    _ => text,
+                }
+            }
+
+            fn block(&mut self, node: &TreeNode) {
+                self.indent += 1;
+                self.stmt(node);
+                self.indent -= 1;
+            }
+
+            fn stmt(&mut self, node: &TreeNode) {
+                match node.statement_type {
+                    StatementType::Program | StatementType::Sequence | StatementType::VariableSeq => {
+                        for child in &node.nodes {
+                            self.stmt(child);
+                        }
+                    }
+                    StatementType::ListVariableDec => {
+                        for var in &node.nodes {
+                            if let Some(init) = var.nodes.first() {
+                                let text = format!("{} = {};", self.var(var), self.bare(init));
+                                self.line(&text);
+                            }
+                        }
+                    }
+                    StatementType::If => {
+                        let text = format!("if ({}) {{", self.bare(&node.nodes[0]));
+                        self.line(&text);
+                        self.block(&node.nodes[1]);
+                        if node.nodes[2].statement_type != StatementType::NoType {
+                            self.line("} else {");
+                            self.block(&node.nodes[2]);
+                        }
+                        self.line("}");
+                    }
+                    StatementType::Repeat => {
+                        self.line("do {");
+                        self.block(&node.nodes[1]);
+                        let text = format!("}} while (!{});", self.expr(&node.nodes[0]));
+                        self.line(&text);
+                    }
+                    StatementType::While => {
+                        let text = format!("while ({}) {{", self.bare(&node.nodes[0]));
+                        self.line(&text);
+                        self.block(&node.nodes[1]);
+                        self.line("}");
+                    }
+                    StatementType::Assignment => {
+                        let text = format!("{} = {};", self.var(&node.nodes[0]), self.bare(&node.nodes[1]));
+                        self.line(&text);
+                    }
+                    StatementType::Read => {
+                        let text = match node.nodes[0].symbol.map(|symbol| self.types[symbol]) {
+                            Some(TinyType::Float) => format!("{} = read_float();", self.var(&node.nodes[0])),
+                            Some(TinyType::Boolean) => format!("{} = read_int() != 0;", self.var(&node.nodes[0])),
+                            Some(_) => format!("{} = read_int();", self.var(&node.nodes[0])),
+                            None => String::from("read_int();"),
+                        };
+                        self.line(&text);
+                    }
+                    StatementType::Write => {
+                        let format = if node.nodes[0].val_type == TinyType::Float { "%g" } else { "%d" };
+                        let text = format!("printf(\"{}\\n\", {});", format, self.bare(&node.nodes[0]));
+                        self.line(&text);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        pub fn generate(node: &TreeNode, st: &SymbolTable, runtime_checks: bool) -> String {
+            let mut names: Vec<String> = Vec::new();
+            for (index, bucket) in st.iter().enumerate() {
+                let name = bucket.name();
+                if !RESERVED.contains(&name) && st.iter().filter(|b| b.name() == name).count() == 1 {
+                    names.push(String::from(name));
+                    continue;
+                }
+                // `x_1` tambien puede ser el nombre de otra variable
+                let mut renamed = format!("{}_{}", name, index);
+                while st.iter().any(|b| b.name() == renamed) || names.contains(&renamed) {
+                    renamed.push('_');
+                }
+                names.push(renamed);
+            }
+            let mut gen = CGen {
+                names,
+                types: st.iter().map(|bucket| bucket.data_type()).collect(),
+                runtime_checks,
+                out: String::from(PRELUDE),
+                indent: 0,
+            };
+            if runtime_checks {
+                gen.out.push_str(CHECKS);
+            }
+            gen.line("");
+            gen.line("int main(void)");
+            gen.line("{");
+            gen.indent += 1;
+            for index in 0..gen.names.len() {
+                let zero = match gen.types[index] {
+                    TinyType::Float => "0.0",
+                    TinyType::Boolean => "false",
+                    _ => "0",
+                };
+                let text = format!("{} {} = {};", c_type(gen.types[index]), gen.names[index], zero);
+                gen.line(&text);
+            }
+            gen.line("");
+            gen.stmt(node);
+            gen.line("return 0;");
+            gen.indent -= 1;
+            gen.line("}");
+            gen.out
+        }
+    }
+
+    // Traduce el arbol revisado a un modulo de WebAssembly en formato de
+    // texto. Las variables, aun las de un bloque, son locales de $main que
+    // empiezan en 0 (como su localidad en TM, ver c_backend), los booleanos
+    // son i32 y read/write son funciones que importa el modulo desde "env":
+    //   read_i32 () -> i32     read_f64 () -> f64
+    //   write_i32 (i32)        write_f64 (f64)
+    // El modulo exporta "main". Con --runtime-checks una revision que falla
+    // escribe el codigo de trampa y la linea y termina con `unreachable`.
+    pub mod wat_backend {
+        use super::analyzer::SymbolTable;
+        use super::ir::{ascii_name, Op};
+        use super::{StatementType, TinyType, TokenType, TreeNode};
+
+        const IMPORTS: &str = "  (import \"env\" \"read_i32\" (func $read_i32 (result i32)))
+  (import \"env\" \"read_f64\" (func $read_f64 (result f64)))
+  (import \"env\" \"write_i32\" (func $write_i32 (param i32)))
+  (import \"env\" \"write_f64\" (func $write_f64 (param f64)))
+";
+
+        const CHECKS: &str = "  (func $trap (param $code i32) (param $line i32)
+    (call $write_i32 (local.get $code))
+    (call $write_i32 (local.get $line))
+    unreachable)
+  (func $checked (param $value i64) (param $line i32) (result i32)
+    (if (i32.or (i64.gt_s (local.get $value) (i64.const 2147483647))
+                (i64.lt_s (local.get $value) (i64.const -2147483648)))
+      (then (call $trap (i32.const 2) (local.get $line))))
+    (i32.wrap_i64 (local.get $value)))
+  (func $checked_add (param $left i32) (param $right i32) (param $line i32) (result i32)
+    (call $checked (i64.add (i64.extend_i32_s (local.get $left)) (i64.extend_i32_s (local.get $right))) (local.get $line)))
+  (func $checked_sub (param $left i32) (param $right i32) (param $line i32) (result i32)
+    (call $checked (i64.sub (i64.extend_i32_s (local.get $left)) (i64.extend_i32_s (local.get $right))) (local.get $line)))
+  (func $checked_mul (param $left i32) (param $right i32) (param $line i32) (result i32)
+    (call $checked (i64.mul (i64.extend_i32_s (local.get $left)) (i64.extend_i32_s (local.get $right))) (local.get $line)))
+  (func $checked_div (param $left i32) (param $right i32) (param $line i32) (result i32)
+    (if (i32.eqz (local.get $right))
+      (then (call $trap (i32.const 1) (local.get $line))))
+    (call $checked (i64.div_s (i64.extend_i32_s (local.get $left)) (i64.extend_i32_s (local.get $right))) (local.get $line)))
+  (func $checked_fdiv (param $left f64) (param $right f64) (param $line i32) (result f64)
+    (if (f64.eq (local.get $right) (f64.const 0))
+      (then (call $trap (i32.const 1) (local.get $line))))
+    (f64.div (local.get $left) (local.get $right)))
+";
+
+        fn wat_type(ty: TinyType) -> &'static str {
+            match ty {
+                TinyType::Float => "f64",
+                _ => "i32",
+            }
+        }
+
+        fn instruction(op: Op, ty: TinyType) -> String {
+            let name = match op {
+                Op::Add => "add",
+                Op::Sub => "sub",
+                Op::Mul => "mul",
+                Op::Div => "div",
+                Op::Lt => "lt",
+                Op::Le => "le",
+                Op::Gt => "gt",
+                Op::Ge => "ge",
+                Op::Eq => "eq",
+                Op::Ne => "ne",
+            };
+            // las operaciones enteras que dependen del signo llevan _s
+            let signed = ty != TinyType::Float && !matches!(op, Op::Add | Op::Sub | Op::Mul | Op::Eq | Op::Ne);
+            format!("{}.{}{}", wat_type(ty), name, if signed { "_s" } else { "" })
+        }
+
+        struct WatGen {
+            names: Vec<String>,
+            types: Vec<TinyType>,
+            runtime_checks: bool,
+            out: String,
+            indent: usize,
+            loops: usize,
+        }
+
+        impl WatGen {
+            fn line(&mut self, text: &str) {
+                for _ in 0..self.indent {
+                    self.out.push_str("  ");
+                }
+                self.out.push_str(text);
+                self.out.push('\n');
+            }
+
+            fn get(&self, node: &TreeNode) -> String {
+                match node.symbol {
+                    Some(symbol) => format!("(local.get ${})", self.names[symbol]),
+                    // una variable no declarada ya se reporto, se usa 0 en su lugar
+                    None => String::from("(i32.const 0)"),
+                }
+            }
+
+            fn set(&mut self, node: &TreeNode, value: &str) {
+                if let Some(symbol) = node.symbol {
+                    let text = format!("(local.set ${} {})", self.names[symbol], value);
+                    self.line(&text);
+                }
+            }
+
+            fn expr(&self, node: &TreeNode) -> String {
+                match node.statement_type {
+                    StatementType::Literal => match node.val_type {
+                        TinyType::Float => format!("(f64.const {:?})", node.float_value()),
+                        _ => format!("(i32.const {})", node.int_value()),
+                    },
+                    StatementType::LiteralBoolExp => {
+                        format!("(i32.const {})", if node.token.lexema == "true" { 1 } else { 0 })
+                    }
+                    StatementType::Variable => self.get(node),
+                    StatementType::Arithmetic | StatementType::Relational => {
+                        let left = self.expr(&node.nodes[0]);
+                        let right = self.expr(&node.nodes[1]);
+                        let op = Op::from_token(&node.token.token).unwrap_or(Op::Add);
+                        let ty = node.nodes[0].val_type;
+                        if self.runtime_checks && !op.is_relational() && ty == TinyType::Integer {
+                            let name = match op {
+                                Op::Add => "checked_add",
+                                Op::Sub => "checked_sub",
+                                Op::Mul => "checked_mul",
+                                _ => "checked_div",
+                            };
+                            format!("(call ${} {} {} (i32.const {}))", name, left, right, node.token.line)
+                        } else if self.runtime_checks && op == Op::Div && ty == TinyType::Float {
+                            format!("(call $checked_fdiv {} {} (i32.const {}))", left, right, node.token.line)
+                        } else {
+                            format!("({} {} {})", instruction(op, ty), left, right)
+                        }
+                    }
+                    StatementType::Cast => {
+                        let value = self.expr(&node.nodes[0]);
+                        match (node.nodes[0].val_type, node.val_type) {
+                            (TinyType::Integer, TinyType::Float) => format!("(f64.convert_i32_s {})", value),
+                            (TinyType::Float, TinyType::Integer) => format!("(i32.trunc_sat_f64_s {})", value),
+                            _ => value,
+                        }
+                    }
+                    StatementType::Not => format!("(i32.eqz {})", self.expr(&node.nodes[0])),
+                    StatementType::BooleanExp => {
+                        // el lado derecho solo se evalua si hace falta
+                        let left = self.expr(&node.nodes[0]);
+                        let right = self.expr(&node.nodes[1]);
+                        if node.token.token == TokenType::TK_OR {
+                            format!("(if (result i32) {} (then (i32.const 1)) (else {}))", left, right)
+                        } else {
+                            format!("(if (result i32) {} (then {}) (else (i32.const 0)))", left, right)
+                        }
+                    }
+                    _ => String::from("(i32.const 0)"),
+                }
+            }
+
+            fn block(&mut self, node: &TreeNode) {
+                self.indent += 1;
+                self.stmt(node);
+                self.indent -= 1;
+            }
+
+            fn stmt(&mut self, node: &TreeNode) {
+                match node.statement_type {
+                    StatementType::Program | StatementType::Sequence | StatementType::VariableSeq => {
+                        for child in &node.nodes {
+                            self.stmt(child);
+                        }
+                    }
+                    StatementType::ListVariableDec => {
+                        for var in &node.nodes {
+                            if let Some(init) = var.nodes.first() {
+                                let value = self.expr(init);
+                                self.set(var, &value);
+                            }
+                        }
+                    }
+                    StatementType::If => {
+                        let text = format!("(if {}", self.expr(&node.nodes[0]));
+                        self.line(&text);
+                        self.indent += 1;
+                        self.line("(then");
+                        self.block(&node.nodes[1]);
+                        self.line(")");
+                        if node.nodes[2].statement_type != StatementType::NoType {
+                            self.line("(else");
+                            self.block(&node.nodes[2]);
+                            self.line(")");
+                        }
+                        self.indent -= 1;
+                        self.line(")");
+                    }
+                    StatementType::Repeat => {
+                        let label = self.loops;
+                        self.loops += 1;
+                        let text = format!("(loop $repeat{}", label);
+                        self.line(&text);
+                        self.block(&node.nodes[1]);
+                        let text = format!("  (br_if $repeat{} (i32.eqz {}))", label, self.expr(&node.nodes[0]));
+                        self.line(&text);
+                        self.line(")");
+                    }
+                    StatementType::While => {
+                        let label = self.loops;
+                        self.loops += 1;
+                        let text = format!("(block $end{}", label);
+                        self.line(&text);
+                        let text = format!("  (loop $while{}", label);
+                        self.line(&text);
+                        let text = format!("    (br_if $end{} (i32.eqz {}))", label, self.expr(&node.nodes[0]));
+                        self.line(&text);
+                        self.indent += 1;
+                        self.block(&node.nodes[1]);
+                        let text = format!("  (br $while{})", label);
+                        self.line(&text);
+                        self.line(")");
+                        self.indent -= 1;
+                        self.line(")");
+                    }
+                    StatementType::Assignment => {
+                        let value = self.expr(&node.nodes[1]);
+                        self.set(&node.nodes[0], &value);
+                    }
+                    StatementType::Read => {
+                        let value = match node.nodes[0].symbol.map(|symbol| self.types[symbol]) {
+                            Some(TinyType::Float) => "(call $read_f64)",
+                            Some(TinyType::Boolean) => "(i32.ne (call $read_i32) (i32.const 0))",
+                            _ => "(call $read_i32)",
+                        };
+                        self.set(&node.nodes[0], value);
+                    }
+                    StatementType::Write => {
+                        let value = self.expr(&node.nodes[0]);
+                        let text = if node.nodes[0].val_type == TinyType::Float {
+                            format!("(call $write_f64 {})", value)
+                        } else {
+                            format!("(call $write_i32 {})", value)
+                        };
+                        self.line(&text);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        pub fn generate(node: &TreeNode, st: &SymbolTable, runtime_checks: bool) -> String {
+            let names: Vec<String> = st
+                .iter()
+                .enumerate()
+                .map(|(index, bucket)| {
+                    let name = bucket.name();
+                    if st.iter().filter(|b| b.name() == name).count() > 1 {
+                        format!("{}.{}", ascii_name(name), index)
+                    } else {
+                        ascii_name(name)
+                    }
+                })
+                .collect();
+            let mut gen = WatGen {
+                names,
+                types: st.iter().map(|bucket| bucket.data_type()).collect(),
+                runtime_checks,
+                out: String::from("(module\n"),
+                indent: 0,
+                loops: 0,
+            };
+            gen.out.push_str(IMPORTS);
+            if runtime_checks {
+                gen.out.push_str(CHECKS);
+            }
+            gen.indent = 1;
+            gen.line("(func $main (export \"main\")");
+            gen.indent = 2;
+            for index in 0..gen.names.len() {
+                let text = format!("(local ${} {})", gen.names[index], wat_type(gen.types[index]));
+                gen.line(&text);
+            }
+            gen.stmt(node);
+            gen.indent = 1;
+            gen.line(")");
+            gen.out.push_str(")\n");
+            gen.out
+        }
+    }
+
+    // Traduce el arbol revisado a IR de LLVM en texto (.ll). Cada variable
+    // tiene un alloca en el bloque de entrada que empieza en 0, los tipos son
+    // i32, double e i1 y read/write llaman a scanf/printf. Los punteros son
+    // `ptr` (LLVM 15 o posterior; LLVM 14 necesita -opaque-pointers).
+    pub mod llvm_backend {
+        use super::analyzer::SymbolTable;
+        use super::ir::{ascii_name, Op};
+        use super::{StatementType, TinyType, TokenType, TreeNode};
+
+        const FORMATS: &str = "@.read.int = private unnamed_addr constant [3 x i8] c\"%d\\00\"
+@.read.float = private unnamed_addr constant [4 x i8] c\"%lf\\00\"
+@.write.int = private unnamed_addr constant [4 x i8] c\"%d\\0A\\00\"
+@.write.float = private unnamed_addr constant [4 x i8] c\"%g\\0A\\00\"
+
+declare i32 @scanf(ptr, ...)
+declare i32 @printf(ptr, ...)
+
+; una lectura que falla da 0, como read_int/read_float del programa C
+define internal i32 @read_int() {
+entry:
+  %value = alloca i32
+  store i32 0, ptr %value
+  %count = call i32 (ptr, ...) @scanf(ptr @.read.int, ptr %value)
+  %ok = icmp eq i32 %count, 1
+  %read = load i32, ptr %value
+  %result = select i1 %ok, i32 %read, i32 0
+  ret i32 %result
+}
+
+define internal double @read_float() {
+entry:
+  %value = alloca double
+  store double 0.0, ptr %value
+  %count = call i32 (ptr, ...) @scanf(ptr @.read.float, ptr %value)
+  %ok = icmp eq i32 %count, 1
+  %read = load double, ptr %value
+  %result = select i1 %ok, double %read, double 0.0
+  ret double %result
+}
+";
+
+        // Con --runtime-checks una revision que falla escribe el codigo de
+        // trampa y la linea y termina, igual que el codigo TM
+        const CHECKS: &str = "declare void @exit(i32)
+declare { i32, i1 } @llvm.sadd.with.overflow.i32(i32, i32)
+declare { i32, i1 } @llvm.ssub.with.overflow.i32(i32, i32)
+declare { i32, i1 } @llvm.smul.with.overflow.i32(i32, i32)
+
+define internal void @trap(i32 %code, i32 %line) noreturn {
+entry:
+  call i32 (ptr, ...) @printf(ptr @.write.int, i32 %code)
+  call i32 (ptr, ...) @printf(ptr @.write.int, i32 %line)
+  call void @exit(i32 1)
+  unreachable
+}
+";
+
+        fn llvm_type(ty: TinyType) -> &'static str {
+            match ty {
+                TinyType::Float => "double",
+                TinyType::Boolean => "i1",
+                _ => "i32",
+            }
+        }
+
+        // Texto para una cadena de LLVM: los bytes que no son ASCII imprimible,
+        // las comillas y la diagonal invertida van como \XX, igual que en FORMATS
+        fn escape(text: &str) -> String {
+            text.bytes()
+                .map(|byte| match byte {
+                    b' '..=b'~' if byte != b'"' && byte != b'\\' => char::from(byte).to_string(),
+                    _ => format!("\\{:02X}", byte),
+                })
+                .collect()
+        }
+
+        // las constantes double van en hexadecimal para que sean exactas
+        fn float_const(value: f64) -> String {
+            format!("0x{:016X}", value.to_bits())
+        }
+
+        fn instruction(op: Op, ty: TinyType) -> &'static str {
+            match (op, ty) {
+                (Op::Add, TinyType::Float) => "fadd",
+                (Op::Sub, TinyType::Float) => "fsub",
+                (Op::Mul, TinyType::Float) => "fmul",
+                (Op::Div, TinyType::Float) => "fdiv",
+                (Op::Lt, TinyType::Float) => "fcmp olt",
+                (Op::Le, TinyType::Float) => "fcmp ole",
+                (Op::Gt, TinyType::Float) => "fcmp ogt",
+                (Op::Ge, TinyType::Float) => "fcmp oge",
+                (Op::Eq, TinyType::Float) => "fcmp oeq",
+                (Op::Ne, TinyType::Float) => "fcmp une",
+                (Op::Add, _) => "add",
+                (Op::Sub, _) => "sub",
+                (Op::Mul, _) => "mul",
+                (Op::Div, _) => "sdiv",
+                (Op::Lt, _) => "icmp slt",
+                (Op::Le, _) => "icmp sle",
+                (Op::Gt, _) => "icmp sgt",
+                (Op::Ge, _) => "icmp sge",
+                (Op::Eq, _) => "icmp eq",
+                (Op::Ne, _) => "icmp ne",
+            }
+        }
+
+        struct LlvmGen {
+            names: Vec<String>,
+            types: Vec<TinyType>,
+            runtime_checks: bool,
+            out: String,
+            temps: usize,
+            labels: usize,
+            // bloque actual, para los phi de and/or
+            block: String,
+        }
+
+        impl LlvmGen {
+            fn emit(&mut self, text: &str) {
+                self.out.push_str("  ");
+                self.out.push_str(text);
+                self.out.push('\n');
+            }
+
+            fn label(&mut self, name: &str) {
+                self.out.push_str(&format!("{}:\n", name));
+                self.block = String::from(name);
+            }
+
+            fn new_temp(&mut self) -> String {
+                self.temps += 1;
+                format!("%t{}", self.temps - 1)
+            }
+
+            fn new_label(&mut self) -> usize {
+                self.labels += 1;
+                self.labels - 1
+            }
+
+            // Salta a la trampa de division entre cero si `right` es 0
+            fn check_divisor(&mut self, ty: TinyType, right: &str, line: u32, label: usize) {
+                let zero = self.new_temp();
+                if ty == TinyType::Float {
+                    self.emit(&format!("{} = fcmp oeq double {}, 0.0", zero, right));
+                } else {
+                    self.emit(&format!("{} = icmp eq i32 {}, 0", zero, right));
+                }
+                self.emit(&format!("br i1 {}, label %trap.div{}, label %check.div{}", zero, label, label));
+                self.label(&format!("trap.div{}", label));
+                self.emit(&format!("call void @trap(i32 1, i32 {})", line));
+                self.emit("unreachable");
+                self.label(&format!("check.div{}", label));
+            }
+
+            // Revisa la operacion entera `op` y salta a una trampa si falla
+            fn checked(&mut self, op: Op, left: &str, right: &str, line: u32) -> String {
+                let label = self.new_label();
+                if op == Op::Div {
+                    self.check_divisor(TinyType::Integer, right, line, label);
+                    let min = self.new_temp();
+                    let minus_one = self.new_temp();
+                    let overflow = self.new_temp();
+                    self.emit(&format!("{} = icmp eq i32 {}, -2147483648", min, left));
+                    self.emit(&format!("{} = icmp eq i32 {}, -1", minus_one, right));
+                    self.emit(&format!("{} = and i1 {}, {}", overflow, min, minus_one));
+                    self.emit(&format!("br i1 {}, label %trap{}, label %ok{}", overflow, label, label));
+                } else {
+                    let name = match op {
+                        Op::Add => "sadd",
+                        Op::Sub => "ssub",
+                        _ => "smul",
+                    };
+                    let pair = self.new_temp();
+                    let overflow = self.new_temp();
+                    self.emit(&format!(
+                        "{} = call {{ i32, i1 }} @llvm.{}.with.overflow.i32(i32 {}, i32 {})",
+                        pair, name, left, right
+                    ));
+                    self.emit(&format!("{} = extractvalue {{ i32, i1 }} {}, 1", overflow, pair));
+                    self.emit(&format!("br i1 {}, label %trap{}, label %ok{}", overflow, label, label));
+                    self.label(&format!("trap{}", label));
+                    self.emit(&format!("call void @trap(i32 2, i32 {})", line));
+                    self.emit("unreachable");
+                    self.label(&format!("ok{}", label));
+                    let result = self.new_temp();
+                    self.emit(&format!("{} = extractvalue {{ i32, i1 }} {}, 0", result, pair));
+                    return result;
+                }
+                self.label(&format!("trap{}", label));
+                self.emit(&format!("call void @trap(i32 2, i32 {})", line));
+                self.emit("unreachable");
+                self.label(&format!("ok{}", label));
+                let result = self.new_temp();
+                self.emit(&format!("{} = sdiv i32 {}, {}", result, left, right));
+                result
+            }
+
+            fn expr(&mut self, node: &TreeNode) -> String {
+                match node.statement_type {
+                    StatementType::Literal => match node.val_type {
+                        TinyType::Float => float_const(node.float_value()),
+                        _ => node.int_value().to_string(),
+                    },
+                    StatementType::LiteralBoolExp => node.token.lexema.clone(),
+                    StatementType::Variable => match node.symbol {
+                        Some(symbol) => {
+                            let value = self.new_temp();
+                            let ty = llvm_type(self.types[symbol]);
+                            self.emit(&format!("{} = load {}, ptr %{}.addr", value, ty, self.names[symbol]));
+                            value
+                        }
+                        // una variable no declarada ya se reporto, se usa 0 en su lugar
+                        None => String::from("0"),
+                    },
+                    StatementType::Arithmetic | StatementType::Relational => {
+                        let left = self.expr(&node.nodes[0]);
+                        let right = self.expr(&node.nodes[1]);
+                        let op = Op::from_token(&node.token.token).unwrap_or(Op::Add);
+                        let ty = node.nodes[0].val_type;
+                        if self.runtime_checks && !op.is_relational() && ty == TinyType::Integer {
+                            return self.checked(op, &left, &right, node.token.line);
+                        }
+                        if self.runtime_checks && op == Op::Div && ty == TinyType::Float {
+                            let label = self.new_label();
+                            self.check_divisor(ty, &right, node.token.line, label);
+                        }
+                        let value = self.new_temp();
+                        self.emit(&format!("{} = {} {} {}, {}", value, instruction(op, ty), llvm_type(ty), left, right));
+                        value
+                    }
+                    StatementType::Cast => {
+                        let src = self.expr(&node.nodes[0]);
+                        let conversion = match (node.nodes[0].val_type, node.val_type) {
+                            (TinyType::Integer, TinyType::Float) => "sitofp i32",
+                            (TinyType::Float, TinyType::Integer) => "fptosi double",
+                            _ => return src,
+                        };
+                        let value = self.new_temp();
+                        self.emit(&format!("{} = {} {} to {}", value, conversion, src, llvm_type(node.val_type)));
+                        value
+                    }
+                    StatementType::Not => {
+                        let src = self.expr(&node.nodes[0]);
+                        let value = self.new_temp();
+                        self.emit(&format!("{} = xor i1 {}, true", value, src));
+                        value
+                    }
+                    StatementType::BooleanExp => {
+                        // el lado derecho solo se evalua si hace falta
+                        let is_or = node.token.token == TokenType::TK_OR;
+                        let name = if is_or { "or" } else { "and" };
+                        let label = self.new_label();
+                        let left = self.expr(&node.nodes[0]);
+                        let left_block = self.block.clone();
+                        let (when_true, when_false) = if is_or {
+                            (format!("{}.end{}", name, label), format!("{}.rhs{}", name, label))
+                        } else {
+                            (format!("{}.rhs{}", name, label), format!("{}.end{}", name, label))
+                        };
+                        self.emit(&format!("br i1 {}, label %{}, label %{}", left, when_true, when_false));
+                        self.label(&format!("{}.rhs{}", name, label));
+                        let right = self.expr(&node.nodes[1]);
+                        let right_block = self.block.clone();
+                        self.emit(&format!("br label %{}.end{}", name, label));
+                        self.label(&format!("{}.end{}", name, label));
+                        let value = self.new_temp();
+                        self.emit(&format!(
+                            "{} = phi i1 [ {}, %{} ], [ {}, %{} ]",
+                            value, is_or, left_block, right, right_block
+                        ));
+                        value
+                    }
+                    _ => String::from("0"),
+                }
+            }
+
+            fn store(&mut self, node: &TreeNode, value: &str) {
+                if let Some(symbol) = node.symbol {
+                    let ty = llvm_type(self.types[symbol]);
+                    self.emit(&format!("store {} {}, ptr %{}.addr", ty, value, self.names[symbol]));
+                }
+            }
+
+            fn stmt(&mut self, node: &TreeNode) {
+                match node.statement_type {
+                    StatementType::Program | StatementType::Sequence | StatementType::VariableSeq => {
+                        for child in &node.nodes {
+                            self.stmt(child);
+                        }
+                    }
+                    StatementType::ListVariableDec => {
+                        for var in &node.nodes {
+                            if let Some(init) = var.nodes.first() {
+                                let value = self.expr(init);
+                                self.store(var, &value);
+                            }
+                        }
+                    }
+                    StatementType::If => {
+                        let label = self.new_label();
+                        let has_else = node.nodes[2].statement_type != StatementType::NoType;
+                        let cond = self.expr(&node.nodes[0]);
+                        let target = if has_else { "else" } else { "endif" };
+                        self.emit(&format!("br i1 {}, label %then{}, label %{}{}", cond, label, target, label));
+                        self.label(&format!("then{}", label));
+                        self.stmt(&node.nodes[1]);
+                        self.emit(&format!("br label %endif{}", label));
+                        if has_else {
+                            self.label(&format!("else{}", label));
+                            self.stmt(&node.nodes[2]);
+                            self.emit(&format!("br label %endif{}", label));
+                        }
+                        self.label(&format!("endif{}", label));
+                    }
+                    StatementType::Repeat => {
+                        let label = self.new_label();
+                        self.emit(&format!("br label %repeat.body{}", label));
+                        self.label(&format!("repeat.body{}", label));
+                        self.stmt(&node.nodes[1]);
+                        let cond = self.expr(&node.nodes[0]);
+                        self.emit(&format!("br i1 {}, label %repeat.end{}, label %repeat.body{}", cond, label, label));
+                        self.label(&format!("repeat.end{}", label));
+                    }
+                    StatementType::While => {
+                        let label = self.new_label();
+                        self.emit(&format!("br label %while.cond{}", label));
+                        self.label(&format!("while.cond{}", label));
+                        let cond = self.expr(&node.nodes[0]);
+                        self.emit(&format!("br i1 {}, label %while.body{}, label %while.end{}", cond, label, label));
+                        self.label(&format!("while.body{}", label));
+                        self.stmt(&node.nodes[1]);
+                        self.emit(&format!("br label %while.cond{}", label));
+                        self.label(&format!("while.end{}", label));
+                    }
+                    StatementType::Assignment => {
+                        let value = self.expr(&node.nodes[1]);
+                        self.store(&node.nodes[0], &value);
+                    }
+                    StatementType::Read => {
+                        let symbol = match node.nodes[0].symbol {
+                            Some(symbol) => symbol,
+                            None => return,
+                        };
+                        let mut value = self.new_temp();
+                        if self.types[symbol] == TinyType::Float {
+                            self.emit(&format!("{} = call double @read_float()", value));
+                        } else {
+                            self.emit(&format!("{} = call i32 @read_int()", value));
+                        }
+                        if self.types[symbol] == TinyType::Boolean {
+                            let number = value;
+                            value = self.new_temp();
+                            self.emit(&format!("{} = icmp ne i32 {}, 0", value, number));
+                        }
+                        self.store(&node.nodes[0], &value);
+                    }
+                    StatementType::Write => {
+                        let mut value = self.expr(&node.nodes[0]);
+                        match node.nodes[0].val_type {
+                            TinyType::Float => {
+                                self.emit(&format!("call i32 (ptr, ...) @printf(ptr @.write.float, double {})", value))
+                            }
+                            ty => {
+                                if ty == TinyType::Boolean {
+                                    let number = self.new_temp();
+                                    self.emit(&format!("{} = zext i1 {} to i32", number, value));
+                                    value = number;
+                                }
+                                self.emit(&format!("call i32 (ptr, ...) @printf(ptr @.write.int, i32 {})", value));
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        pub fn generate(node: &TreeNode, st: &SymbolTable, source_name: &str, runtime_checks: bool) -> String {
+            let names: Vec<String> = st
+                .iter()
+                .enumerate()
+                .map(|(index, bucket)| {
+                    let name = bucket.name();
+                    if st.iter().filter(|b| b.name() == name).count() > 1 {
+                        format!("{}.{}", ascii_name(name), index)
+                    } else {
+                        ascii_name(name)
+                    }
+                })
+                .collect();
+            let mut gen = LlvmGen {
+                names,
+                types: st.iter().map(|bucket| bucket.data_type()).collect(),
+                runtime_checks,
+                out: format!("; ModuleID = '{0}'\nsource_filename = \"{0}\"\n\n", escape(source_name)),
+                temps: 0,
+                labels: 0,
+                block: String::new(),
+            };
+            gen.out.push_str(FORMATS);
+            if runtime_checks {
+                gen.out.push('\n');
+                gen.out.push_str(CHECKS);
+            }
+            gen.out.push_str("\ndefine i32 @main() {\n");
+            gen.label("entry");
+            for index in 0..gen.names.len() {
+                let ty = llvm_type(gen.types[index]);
+                let zero = match gen.types[index] {
+                    TinyType::Float => "0.0",
+                    TinyType::Boolean => "false",
+                    _ => "0",
+                };
+                let name = gen.names[index].clone();
+                gen.emit(&format!("%{}.addr = alloca {}", name, ty));
+                gen.emit(&format!("store {} {}, ptr %{}.addr", ty, zero, name));
+            }
+            gen.stmt(node);
+            gen.emit("ret i32 0");
+            gen.out.push_str("}\n");
+            gen.out
+        }
+    }
+
+    pub mod analyzer {
+        use super::diagnostics::{error, ErrorCode};
+        use super::BucketList;
+        use super::Reference;
+        use super::ReferenceKind;
+        use super::StatementType;
+        use super::TinyType;
+        use super::Token;
+        use super::TreeNode;
+        use std::collections::HashMap;
+
+        // Cadena JSON: comillas, diagonal invertida y caracteres de control
+        // escapados; lo que no es ASCII queda tal cual en UTF-8
+        fn json_string(text: &str) -> String {
+            let mut out = String::from("\"");
+            for c in text.chars() {
+                match c {
+                    '"' => out.push_str("\\\""),
+                    '\\' => out.push_str("\\\\"),
+                    '\n' => out.push_str("\\n"),
+                    '\t' => out.push_str("\\t"),
+                    c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+                    c => out.push(c),
+                }
+            }
+            out.push('"');
+            out
+        }
+
+        // Campo CSV (RFC 4180): entre comillas, con las comillas duplicadas, si
+        // tiene comas, comillas o saltos de linea
+        fn csv_field(text: &str) -> String {
+            if text.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", text.replace('"', "\"\""))
+            } else {
+                String::from(text)
+            }
+        }
+
+        // Los simbolos se guardan en orden de declaracion; cada bloque abierto tiene un
+        // HashMap que solo indexa por nombre, asi la salida no depende de su orden.
+        pub struct SymbolTable {
+            scopes: Vec<HashMap<String, usize>>,
+            // Por cada bloque abierto, los nombres que declara mas adelante y su linea
+            pending: Vec<HashMap<String, u32>>,
+            symbols: Vec<BucketList>,
+            pub init_mem: u32,
+            // Localidades de datos que usa el programa. Cada simbolo tiene la suya,
+            // aunque su bloque ya se haya cerrado: la memoria de TM empieza en 0 y
+            // una localidad compartida dejaria en una variable sin inicializar el
+            // valor de otra.
+            pub mem_size: u32,
+        }
+
+        impl SymbolTable {
+            pub fn new() -> SymbolTable {
+                return SymbolTable {
+                    scopes: vec![HashMap::new()],
+                    pending: vec![HashMap::new()],
+                    symbols: vec![],
+                    init_mem: 0,
+                    mem_size: 0,
+                };
+            }
+
+            // Simbolos en orden de declaracion
+            pub fn iter(&self) -> std::slice::Iter<'_, BucketList> {
+                self.symbols.iter()
+            }
+
+            pub fn get(&self, index: usize) -> &BucketList {
+                &self.symbols[index]
+            }
+
+            // Simbolo al que build_table resolvio una variable del arbol
+            pub fn symbol_of(&self, node: &TreeNode) -> Option<&BucketList> {
+                node.symbol.map(|index| &self.symbols[index])
+            }
+
+            pub fn enter_scope(&mut self) {
+                self.scopes.push(HashMap::new());
+                self.pending.push(HashMap::new());
+            }
+
+            pub fn exit_scope(&mut self) {
+                self.scopes.pop();
+                self.pending.pop();
+            }
+
+            // Busca el nombre del bloque mas interno hacia afuera
+            pub fn resolve(&self, name: &str) -> Option<usize> {
+                self.scopes.iter().rev().find_map(|scope| scope.get(name).copied())
+            }
+
+            pub fn insert(&mut self, token: &Token, loc: u32, data_type: TinyType) -> Option<usize> {
+                let name: &str = &token.lexema;
+                if self.scopes.last().unwrap().contains_key(name) {
+                    self.declarationError(
+                        ErrorCode::DoubleDeclaration,
+                        name,
+                        token.line,
+                        "variable double declaration",
+                    );
+                    return None;
+                }
+                let index = self.symbols.len();
+                let new_variable: BucketList = BucketList {
+                    name: String::from(name),
+                    references: vec![Reference::new(token, ReferenceKind::Declared)],
+                    mem_location: loc,
+                    data_type,
+                    depth: (self.scopes.len() - 1) as u32,
+                    shadows: self.resolve(name),
+                };
+                self.scopes.last_mut().unwrap().insert(String::from(name), index);
+                self.symbols.push(new_variable);
+                Some(index)
+            }
+
+            pub fn build_table(&mut self, node: &mut TreeNode) {
+                match node.statement_type {
+                    StatementType::Sequence => {
+                        self.enter_scope();
+                        self.build_block(node);
+                        self.exit_scope();
+                    }
+                    // El cuerpo del programa no abre un bloque: comparte el de las
+                    // declaraciones iniciales
+                    StatementType::Program => {
+                        self.build_table(&mut node.nodes[0]);
+                        self.build_block(&mut node.nodes[1]);
+                    }
+                    StatementType::VariableSeq
+                    | StatementType::If
+                    | StatementType::Repeat
+                    | StatementType::While
+                    | StatementType::Read
+                    | StatementType::Write
+                    | StatementType::Not
+                    | StatementType::Cast => {
+                        for nodes in &mut node.nodes {
+                            self.build_table(nodes);
+                        }
+                    }
+                    StatementType::ListVariableDec => {
+                        let val_type = node.val_type;
+                        for nodes in &mut node.nodes {
+                            // El inicializador se resuelve antes de declarar la variable,
+                            // asi `int x = x;` en un bloque usa la `x` de afuera
+                            for init in &mut nodes.nodes {
+                                self.build_table(init);
+                            }
+                            nodes.symbol = self.insert(&nodes.token, self.init_mem, val_type);
+                            self.init_mem += 1;
+                            self.mem_size = self.init_mem;
+                            if let (Some(index), false) = (nodes.symbol, nodes.nodes.is_empty()) {
+                                self.symbols[index]
+                                    .references
+                                    .push(Reference::new(&nodes.token, ReferenceKind::Written));
+                            }
+                        }
+                    }
+                    StatementType::Assignment
+                    | StatementType::Arithmetic
+                    | StatementType::Relational
+                    | StatementType::BooleanExp => {
+                        self.build_table(&mut node.nodes[0]);
+                        self.build_table(&mut node.nodes[1]);
+                    }
+                    StatementType::Variable => {
+                        let kind = if node.is_lvalue {
+                            ReferenceKind::Written
+                        } else {
+                            ReferenceKind::Read
+                        };
+                        node.symbol = self.lookup(&node.token, kind);
+                    }
+                    _ => {}
+                }
+            }
+
+            // Sentencias de un bloque en el bloque abierto mas interno
+            fn build_block(&mut self, node: &mut TreeNode) {
+                let pending = self.pending.last_mut().unwrap();
+                for stmt in &node.nodes {
+                    if stmt.statement_type == StatementType::ListVariableDec {
+                        for var in &stmt.nodes {
+                            pending.entry(var.token.lexema.clone()).or_insert(var.token.line);
+                        }
+                    }
+                }
+                for nodes in &mut node.nodes {
+                    self.build_table(nodes);
+                }
+            }
+
+            fn error_msg(&mut self, code: ErrorCode, label: &str, name: &str, line: u32, msg: &str) {
+                error(code, line, &format!("{}, msg: {} for {}", label, msg, name));
+            }
+
+            fn declarationError(&mut self, code: ErrorCode, name: &str, line: u32, msg: &str) {
+                self.error_msg(code, "symbol table", name, line, msg);
+            }
+
+            pub fn lookup(&mut self, token: &Token, kind: ReferenceKind) -> Option<usize> {
+                match self.resolve(&token.lexema) {
+                    Some(index) => {
+                        let references = &mut self.symbols[index].references;
+                        references.push(Reference::new(token, kind));
+                        references.sort_by_key(|r| (r.line, r.column));
+                        Some(index)
+                    }
+                    None => {
+                        let later = self
+                            .pending
+                            .iter()
+                            .rev()
+                            .find_map(|scope| scope.get(&token.lexema).copied());
+                        match later {
+                            Some(line) => self.declarationError(
+                                ErrorCode::UseBeforeDeclaration,
+                                &token.lexema,
+                                token.line,
+                                &format!("variable used before its declaration on line {}", line),
+                            ),
+                            None => self.declarationError(
+                                ErrorCode::UndeclaredVariable,
+                                &token.lexema,
+                                token.line,
+                                "variable no declared",
+                            ),
+                        }
+                        None
+                    }
+                }
+            }
+
+            pub fn print(&mut self) {
+                print!("{}", self.to_text());
+            }
+
+            pub fn to_text(&self) -> String {
+                let mut out = String::from("TABLA DE SIMBOLOS\n");
+                out.push_str(&format!(
+                    "{:<15}{:<10}{:<11}{:<10}Line Numbers\n",
+                    "Variable Name", "Location", "Data Type", "Declared"
+                ));
+                out.push_str(&format!(
+                    "{:<15}{:<10}{:<11}{:<10}************\n",
+                    "*************", "********", "*********", "********"
+                ));
+                for bucket in self.iter() {
+                    let lines: Vec<String> =
+                        bucket.lines().iter().map(|line| line.to_string()).collect();
+                    let data_type = format!("{:?}", bucket.data_type());
+                    let declared = format!(
+                        "{}:{}",
+                        bucket.declaration().line,
+                        bucket.declaration().column
+                    );
+                    out.push_str(&format!(
+                        "{:<15}{:<10}{:<11}{:<10}{}\n",
+                        bucket.name(),
+                        bucket.mem_location(),
+                        data_type,
+                        declared,
+                        lines.join(", ")
+                    ));
+                }
+                out
+            }
+
+            pub fn to_json(&self) -> String {
+                let mut out = String::from("{\n  \"symbols\": [");
+                for (i, bucket) in self.iter().enumerate() {
+                    out.push_str(if i == 0 { "\n" } else { ",\n" });
+                    out.push_str(&format!(
+                        "    {{\n      \"name\": {},\n      \"type\": \"{}\",\n      \"location\": {},\n      \"depth\": {},\n      \"references\": [",
+                        json_string(bucket.name()),
+                        bucket.data_type().name(),
+                        bucket.mem_location(),
+                        bucket.depth()
+                    ));
+                    for (j, r) in bucket.references().iter().enumerate() {
+                        out.push_str(if j == 0 { "\n" } else { ",\n" });
+                        out.push_str(&format!(
+                            "        {{ \"kind\": \"{}\", \"line\": {}, \"column\": {}, \"end_column\": {} }}",
+                            r.kind.name(),
+                            r.line,
+                            r.column,
+                            r.end_column
+                        ));
+                    }
+                    out.push_str("\n      ]\n    }");
+                }
+                out.push_str("\n  ]\n}\n");
+                out
+            }
+
+            // Una fila por referencia
+            pub fn to_csv(&self) -> String {
+                let mut out = String::from("name,type,location,depth,kind,line,column,end_column\n");
+                for bucket in self.iter() {
+                    for r in bucket.references() {
+                        out.push_str(&format!(
+                            "{},{},{},{},{},{},{},{}\n",
+                            csv_field(bucket.name()),
+                            bucket.data_type().name(),
+                            bucket.mem_location(),
+                            bucket.depth(),
+                            r.kind.name(),
+                            r.line,
+                            r.column,
+                            r.end_column
+                        ));
+                    }
+                }
+                out
+            }
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use super::{csv_field, json_string};
+
+            // los identificadores de Tiny no tienen estos caracteres, pero la
+            // salida tiene que ser valida con cualquier nombre
+            #[test]
+            fn names_are_escaped() {
+                assert_eq!(json_string("año"), "\"año\"");
+                assert_eq!(json_string("a\"b\\c\nd\u{1}"), "\"a\\\"b\\\\c\\nd\\u0001\"");
+                assert_eq!(csv_field("año"), "año");
+                assert_eq!(csv_field("a,b"), "\"a,b\"");
+                assert_eq!(csv_field("a\"b"), "\"a\"\"b\"");
+                assert_eq!(csv_field("a\nb"), "\"a\nb\"");
+            }
+        }
+    }
+
+    pub mod checker {
+        use super::analyzer::SymbolTable;
+        use super::diagnostics::{error, ErrorCode};
+        use super::new_cast;
+        use super::StatementType;
+        use super::TinyType;
+        use super::Token;
+        use super::TokenType;
+        use super::TreeNode;
+
+        fn is_numeric(val_type: TinyType) -> bool {
+            val_type == TinyType::Integer || val_type == TinyType::Float
+        }
+
+        // Tipo comun de una operacion entre numeros: int se promueve a float
+        fn common_type(left: TinyType, right: TinyType) -> TinyType {
+            if left == TinyType::Float || right == TinyType::Float {
+                TinyType::Float
+            } else {
+                TinyType::Integer
+            }
+        }
+
+        // Envuelve un valor int en una conversion implicita cuando se necesita un float
+        fn widen(node: &mut TreeNode, target: TinyType) {
+            if node.val_type == TinyType::Integer && target == TinyType::Float {
+                let token = Token {
+                    token: TokenType::NoToken,
+                    lexema: String::from("float"),
+                    line: node.token.line,
+                    column: node.token.column,
+                };
+                *node = new_cast(&token, node, TinyType::Float);
+            }
+        }
+
+        // Las variables numericas aceptan numeros (un float en un int necesita conversion
+        // explicita) y las booleanas solo booleanos
+        fn check_store(target: TinyType, value: &mut TreeNode) -> Result<(), ErrorCode> {
+            if is_numeric(target) {
+                if target == TinyType::Integer && value.val_type == TinyType::Float {
+                    return Err(ErrorCode::FloatToIntWithoutCast);
+                }
+                if !is_numeric(value.val_type) {
+                    return Err(ErrorCode::AssignmentTypeMismatch);
+                }
+                widen(value, target);
+                Ok(())
+            } else if value.val_type == TinyType::Boolean {
+                Ok(())
+            } else {
+                Err(ErrorCode::AssignmentTypeMismatch)
+            }
+        }
+
+        // Un operando sin tipo viene de un error que ya se reporto (una variable
+        // no declarada o una operacion invalida); no se reporta otro por el
+        fn typed(operands: &[TreeNode]) -> bool {
+            operands.iter().all(|operand| operand.val_type != TinyType::NoType)
+        }
+
+        fn postProc(node: &mut TreeNode, sym_table: &mut SymbolTable) {
+            match node.statement_type {
+                StatementType::If if typed(&node.nodes[..1]) => {
+                    if node.nodes[0].val_type != TinyType::Boolean {
+                        error(
+                            ErrorCode::NonBooleanIfCondition,
+                            node.token.line,
+                            "if condition is not boolean",
+                        );
+                    }
+                }
+                StatementType::While if typed(&node.nodes[..1]) => {
+                    if node.nodes[0].val_type != TinyType::Boolean {
+                        error(
+                            ErrorCode::NonBooleanWhileCondition,
+                            node.token.line,
+                            "while condition is not boolean",
+                        );
+                    }
+                }
+                StatementType::Repeat if typed(&node.nodes[..1]) => {
+                    if node.nodes[0].val_type != TinyType::Boolean {
+                        error(
+                            ErrorCode::NonBooleanUntilCondition,
+                            node.token.line,
+                            "repeat condition is not boolean",
+                        );
+                    }
+                }
+                StatementType::Not if typed(&node.nodes[..1]) => {
+                    if node.nodes[0].val_type != TinyType::Boolean {
+                        error(
+                            ErrorCode::NonBooleanNotOperand,
+                            node.token.line,
+                            "not in not a boolean value",
+                        );
+                    }
+                }
+
+                StatementType::BooleanExp if typed(&node.nodes[..2]) => {
+                    if node.nodes[0].val_type == TinyType::Boolean
+                        && node.nodes[1].val_type == TinyType::Boolean {
+                            node.val_type = TinyType::Boolean;
+                    } else{
+                        error(
+                            ErrorCode::NonBooleanLogicalOperand,
+                            node.token.line,
+                            &format!(
+                                "cannot compare {:?} to {:?} for {}",
+                                node.nodes[0].val_type,
+                                node.nodes[1].val_type,
+                                &node.nodes[0].token.lexema
+                            ),
+                        );
+                    }
+                }
+
+                StatementType::Arithmetic if typed(&node.nodes[..2]) => {
+                    if is_numeric(node.nodes[0].val_type) && is_numeric(node.nodes[1].val_type) {
+                        node.val_type = common_type(node.nodes[0].val_type, node.nodes[1].val_type);
+                        widen(&mut node.nodes[0], node.val_type);
+                        widen(&mut node.nodes[1], node.val_type);
+                    }
+                    else{
+                        error(
+                            ErrorCode::InvalidArithmeticOperand,
+                            node.token.line,
+                            &format!(
+                                "cannot do operation {:?} on {:?} for {}",
+                                node.nodes[0].val_type,
+                                node.nodes[1].val_type,
+                                &node.nodes[0].token.lexema
+                            ),
+                        );
+                    }
+                }
+                StatementType::Relational if typed(&node.nodes[..2]) => {
+                    if is_numeric(node.nodes[0].val_type) && is_numeric(node.nodes[1].val_type) {
+                            let operands = common_type(node.nodes[0].val_type, node.nodes[1].val_type);
+                            widen(&mut node.nodes[0], operands);
+                            widen(&mut node.nodes[1], operands);
+                            node.val_type = TinyType::Boolean;
+                        }
+                        else{
+                            error(
+                                ErrorCode::InvalidComparisonOperand,
+                                node.token.line,
+                                &format!(
+                                    "cannot compare {:?} to {:?} for {}",
+                                    node.nodes[0].val_type,
+                                    node.nodes[1].val_type,
+                                    &node.nodes[0].token.lexema
+                                ),
+                            );
+                        }
+                }
+                StatementType::Cast if typed(&node.nodes[..1]) && !is_numeric(node.nodes[0].val_type) => {
+                    error(
+                        ErrorCode::InvalidCastOperand,
+                        node.token.line,
+                        &format!("cannot convert {:?} to {:?}", node.nodes[0].val_type, node.val_type),
+                    );
+                }
+                StatementType::ListVariableDec => {
+                    for var in &mut node.nodes {
+                        let target = var.val_type;
+                        if !typed(&var.nodes) {
+                            continue;
+                        }
+                        if let Some(init) = var.nodes.first_mut() {
+                            let value = init.val_type;
+                            if let Err(code) = check_store(target, init) {
+                                error(
+                                    code,
+                                    var.token.line,
+                                    &format!(
+                                        "cannot initialize {:?} with {:?} for {}",
+                                        target, value, &var.token.lexema
+                                    ),
+                                );
+                            }
+                        }
+                    }
+                }
+                StatementType::Assignment if typed(&node.nodes[..2]) => {
+                    let target = node.nodes[0].val_type;
+                    let value = node.nodes[1].val_type;
+                    match check_store(target, &mut node.nodes[1]) {
+                        Ok(()) => node.val_type = target,
+                        Err(code) => error(
+                            code,
+                            node.token.line,
+                            &format!(
+                                "cannot assign {:?} to {:?} for {}",
+                                value,
+                                target,
+                                &node.nodes[0].token.lexema
+                            ),
+                        ),
+                    }
+                }
+                StatementType::Variable => {
+                    if let Some(var) = sym_table.symbol_of(node) {
+                        node.val_type = var.data_type;
+                    }
+                }
+                // Se sigue con 0 para que las fases siguientes puedan leer el literal
+                StatementType::Literal
+                    if node.val_type == TinyType::Integer && node.token.lexema.parse::<i32>().is_err() =>
+                {
+                    error(
+                        ErrorCode::IntegerLiteralOutOfRange,
+                        node.token.line,
+                        &format!("integer literal {} does not fit in 32 bits", node.token.lexema),
+                    );
+                    node.token.lexema = String::from("0");
+                }
+                _ => {}
+            }
+        }
+
+        pub fn typeChecking(node: &mut TreeNode, sym_table: &mut SymbolTable) {
+            for child in &mut node.nodes {
+                typeChecking(child, sym_table);
+            }
+            postProc(node, sym_table);
+        }
+    }
+
+    // Evaluacion de expresiones constantes sobre el arbol ya revisado
+    pub mod fold {
+        use super::diagnostics::{error, ErrorCode};
+        use super::new_literal;
+        use super::new_literal_boolean;
+        use super::StatementType;
+        use super::TinyType;
+        use super::TokenType;
+        use super::TreeNode;
+        use std::cmp::Ordering;
+
+        #[derive(Debug, Copy, Clone, PartialEq)]
+        enum Value {
+            Int(i32),
+            Float(f64),
+            Bool(bool),
+        }
+
+        fn value_of(node: &TreeNode) -> Option<Value> {
+            match node.statement_type {
+                StatementType::Literal => match node.val_type {
+                    TinyType::Integer => node.token.lexema.parse().ok().map(Value::Int),
+                    TinyType::Float => node.token.lexema.parse().ok().map(Value::Float),
+                    _ => None,
+                },
+                StatementType::LiteralBoolExp => Some(Value::Bool(node.token.lexema == "true")),
+                _ => None,
+            }
+        }
+
+        // Nodo literal para `value`; None si la constante no se puede cargar
+        // con LDC (ver emit_float_consts en codegen)
+        fn literal(node: &TreeNode, value: Value) -> Option<TreeNode> {
+            let mut token = node.token.copy_token();
+            match value {
+                Value::Int(v) => {
+                    token.token = TokenType::TK_NUM;
+                    token.lexema = v.to_string();
+                    Some(new_literal(&token, TinyType::Integer))
+                }
+                Value::Float(v) => {
+                    if !v.is_finite() {
+                        return None;
+                    }
+                    let mut lexema = v.to_string();
+                    if !lexema.contains('.') {
+                        lexema.push_str(".0");
+                    }
+                    let (int_part, frac_part) = lexema.split_at(lexema.find('.').unwrap());
+                    let frac_part = &frac_part[1..];
+                    if frac_part.len() > 9 || format!("{}{}", int_part, frac_part).parse::<i32>().is_err() {
+                        return None;
+                    }
+                    token.token = TokenType::TK_DECIMAL;
+                    token.lexema = lexema;
+                    Some(new_literal(&token, TinyType::Float))
+                }
+                Value::Bool(v) => {
+                    token.token = if v { TokenType::TK_TRUE } else { TokenType::TK_FALSE };
+                    token.lexema = v.to_string();
+                    Some(new_literal_boolean(&token))
+                }
+            }
+        }
+
+        fn arithmetic(op: &TokenType, left: Value, right: Value) -> Option<Value> {
+            match (left, right) {
+                (Value::Int(l), Value::Int(r)) => match op {
+                    TokenType::TK_PLUS => l.checked_add(r).map(Value::Int),
+                    TokenType::TK_MINUS => l.checked_sub(r).map(Value::Int),
+                    TokenType::TK_TIMES => l.checked_mul(r).map(Value::Int),
+                    TokenType::TK_OVER => l.checked_div(r).map(Value::Int),
+                    _ => None,
+                },
+                (Value::Float(l), Value::Float(r)) => match op {
+                    TokenType::TK_PLUS => Some(Value::Float(l + r)),
+                    TokenType::TK_MINUS => Some(Value::Float(l - r)),
+                    TokenType::TK_TIMES => Some(Value::Float(l * r)),
+                    TokenType::TK_OVER if r != 0.0 => Some(Value::Float(l / r)),
+                    _ => None,
+                },
+                _ => None,
+            }
+        }
+
+        fn relational(op: &TokenType, left: Value, right: Value) -> Option<Value> {
+            let order = match (left, right) {
+                (Value::Int(l), Value::Int(r)) => l.cmp(&r),
+                (Value::Float(l), Value::Float(r)) => l.partial_cmp(&r)?,
+                (Value::Bool(l), Value::Bool(r)) => l.cmp(&r),
+                _ => return None,
+            };
+            let result = match op {
+                TokenType::TK_LT => order == Ordering::Less,
+                TokenType::TK_LTE => order != Ordering::Greater,
+                TokenType::TK_GT => order == Ordering::Greater,
+                TokenType::TK_GTE => order != Ordering::Less,
+                TokenType::TK_EQ => order == Ordering::Equal,
+                TokenType::TK_DIF => order != Ordering::Equal,
+                _ => return None,
+            };
+            Some(Value::Bool(result))
+        }
+
+        fn cast(target: TinyType, value: Value) -> Option<Value> {
+            match (value, target) {
+                (Value::Int(v), TinyType::Float) => Some(Value::Float(v.into())),
+                (Value::Float(v), TinyType::Integer) => {
+                    let v = v.trunc();
+                    if v >= i32::MIN.into() && v <= i32::MAX.into() {
+                        Some(Value::Int(v as i32))
+                    } else {
+                        None
+                    }
+                }
+                _ => None,
+            }
+        }
+
+        fn is_zero(value: Option<Value>) -> bool {
+            match value {
+                Some(Value::Int(v)) => v == 0,
+                Some(Value::Float(v)) => v == 0.0,
+                _ => false,
+            }
+        }
+
+        // Reemplaza las expresiones constantes por su valor. Una condicion
+        // constante queda como literal y el codigo intermedio de if y while
+        // ya no salta a la rama que nunca se ejecuta (ver deadcode).
+        // Con `report` se marca la division entre un cero constante y el
+        // desbordamiento de una operacion entre enteros constantes.
+        pub fn fold(node: &mut TreeNode, report: bool) {
+            for child in &mut node.nodes {
+                fold(child, report);
+            }
+            let replacement = match node.statement_type {
+                StatementType::Arithmetic => {
+                    let right = value_of(&node.nodes[1]);
+                    if node.token.token == TokenType::TK_OVER && is_zero(right) {
+                        if report {
+                            error(ErrorCode::ConstantArithmeticError, node.token.line, "division by a constant zero");
+                        }
+                        None
+                    } else {
+                        match (value_of(&node.nodes[0]), right) {
+                            (Some(left), Some(right)) => match arithmetic(&node.token.token, left, right) {
+                                Some(value) => literal(node, value),
+                                None => {
+                                    if report && matches!((left, right), (Value::Int(_), Value::Int(_))) {
+                                        error(
+                                            ErrorCode::ConstantArithmeticError,
+                                            node.token.line,
+                                            "integer overflow in a constant expression",
+                                        );
+                                    }
+                                    None
+                                }
+                            },
+                            _ => None,
+                        }
+                    }
+                }
+                StatementType::Relational => match (value_of(&node.nodes[0]), value_of(&node.nodes[1])) {
+                    (Some(left), Some(right)) => relational(&node.token.token, left, right)
+                        .and_then(|value| literal(node, value)),
+                    _ => None,
+                },
+                StatementType::Cast => value_of(&node.nodes[0])
+                    .and_then(|value| cast(node.val_type, value))
+                    .and_then(|value| literal(&node.nodes[0], value)),
+                StatementType::Not => match value_of(&node.nodes[0]) {
+                    Some(Value::Bool(v)) => literal(node, Value::Bool(!v)),
+                    _ => None,
+                },
+                StatementType::BooleanExp => {
+                    // el lado izquierdo se evalua primero, si es constante decide
+                    // el resultado o lo deja en manos del lado derecho
+                    let is_or = node.token.token == TokenType::TK_OR;
+                    match value_of(&node.nodes[0]) {
+                        Some(Value::Bool(v)) if v == is_or => literal(node, Value::Bool(v)),
+                        Some(Value::Bool(_)) => Some(node.nodes[1].copy()),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            };
+            if let Some(replacement) = replacement {
+                *node = replacement;
+            }
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use crate::tests::lower_source;
+
+            #[test]
+            fn constant_expressions_are_folded() {
+                let source = "program {
+    int x;
+    read x;
+    x = 2 * 3 + 4;
+    write x * (10 - 7);
+    write float(7) / 2;
+    write not (1 == 1);
+    if (1 < 2) then {
+        write 1;
+    } else {
+        write 2;
+    } fi
+    while (3 > 4) {
+        write 3;
+    }
+}
+";
+                // sin dead-code las ramas que no se ejecutan siguen ahi, pero
+                // ya no hay saltos condicionales
+                let expected = "var x: int @0
+temp t0: int
+    read x
+    x = 10
+    t0 = x * 3
+    write t0
+    write 3.5
+    write false
+    write 1
+    goto L1
+L0:
+    write 2
+L1:
+L2:
+    goto L3
+    write 3
+    goto L2
+L3:
+";
+                assert_eq!(lower_source("folded", source).to_text(), expected);
+            }
+
+            // Las operaciones con una variable se quedan para que las revise
+            // --runtime-checks, aunque un operando sea constante
+            #[test]
+            fn operations_on_variables_are_kept() {
+                let source = "program {
+    int a, b;
+    read a;
+    read b;
+    write a / (b * (4 - 3));
+    write a * (1000 * 1000);
+}
+";
+                let expected = "var a: int @0
+var b: int @1
+temp t0: int
+temp t1: int
+temp t2: int
+    read a
+    read b
+    t0 = b * 1
+    t1 = a / t0
+    write t1
+    t2 = a * 1000000
+    write t2
+";
+                assert_eq!(lower_source("kept", source).to_text(), expected);
+            }
+        }
+    }
+
+    // Condiciones constantes y codigo que nunca se ejecuta
+    pub mod deadcode {
+        use super::cfg::Cfg;
+        use super::ir::{Instr, IrProgram};
+        use super::lints::{Lint, LintConfig};
+        use super::StatementType;
+        use super::TreeNode;
+
+        // Revisa las condiciones del arbol despues de evaluar las constantes
+        pub fn check_conditions(node: &TreeNode, config: &LintConfig) {
+            let name = match node.statement_type {
+                StatementType::If => "if",
+                StatementType::While => "while",
+                StatementType::Repeat => "do ... until",
+                _ => "",
+            };
+            if !name.is_empty() && node.nodes[0].statement_type == StatementType::LiteralBoolExp {
+                config.emit(
+                    Lint::ConstantConditions,
+                    node.token.line,
+                    &format!("{} condition is always {}", name, node.nodes[0].token.lexema),
+                );
+            }
+            for child in &node.nodes {
+                check_conditions(child, config);
+            }
+        }
+
+        fn has_code(program: &IrProgram, cfg: &Cfg, block: usize) -> bool {
+            let block = &cfg.blocks[block];
+            program.code[block.start..block.end]
+                .iter()
+                .any(|quad| !matches!(quad.instr, Instr::Label(_)))
+        }
+
+        // Reporta el inicio de cada region del grafo a la que no se llega
+        // desde la entrada
+        pub fn check_unreachable(program: &IrProgram, cfg: &Cfg, config: &LintConfig) {
+            let reachable = cfg.reachable();
+            let mut reported = Vec::new();
+            for (index, block) in cfg.blocks.iter().enumerate() {
+                if reachable[index] || !has_code(program, cfg, index) {
+                    continue;
+                }
+                let head = block
+                    .preds
+                    .iter()
+                    .all(|&pred| reachable[pred] || !has_code(program, cfg, pred));
+                let line = program.code[block.start..block.end]
+                    .iter()
+                    .find(|quad| !matches!(quad.instr, Instr::Label(_)))
+                    .map_or(0, |quad| quad.line);
+                if head && !reported.contains(&line) {
+                    reported.push(line);
+                    config.emit(Lint::UnreachableCode, line, "unreachable statement");
+                }
+            }
+        }
+
+        // Quita los bloques a los que no se llega y los saltos a la
+        // instruccion siguiente que quedan despues de quitarlos
+        pub fn remove_unreachable(program: &mut IrProgram, cfg: &Cfg) {
+            let reachable = cfg.reachable();
+            let mut code = Vec::new();
+            for (index, block) in cfg.blocks.iter().enumerate() {
+                if reachable[index] {
+                    code.extend_from_slice(&program.code[block.start..block.end]);
+                }
+            }
+            let mut index = 0;
+            while index < code.len() {
+                let redundant = match code[index].instr {
+                    Instr::Goto(label) => code[index + 1..]
+                        .iter()
+                        .take_while(|quad| matches!(quad.instr, Instr::Label(_)))
+                        .any(|quad| quad.instr == Instr::Label(label)),
+                    _ => false,
+                };
+                if redundant {
+                    code.remove(index);
+                } else {
+                    index += 1;
+                }
+            }
+            program.code = code;
+        }
+    }
+
+    // Optimizaciones con nombre. El nivel (-O0, -O1, -O2) decide cuales
+    // corren y cada una se puede activar o desactivar por separado para
+    // encontrar la que genera codigo incorrecto.
+    pub mod passes {
+        use std::collections::HashMap;
+        use std::time::{Duration, Instant};
+
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+        pub enum Pass {
+            Fold,
+            DeadCode,
+            RegAlloc,
+            Peephole,
+        }
+
+        // en el orden en que corren
+        pub const ALL_PASSES: [Pass; 4] = [Pass::Fold, Pass::DeadCode, Pass::RegAlloc, Pass::Peephole];
+
+        impl Pass {
+            pub fn name(&self) -> &'static str {
+                match self {
+                    Pass::Fold => "fold",
+                    Pass::DeadCode => "dead-code",
+                    Pass::RegAlloc => "regalloc",
+                    Pass::Peephole => "peephole",
+                }
+            }
+
+            pub fn from_name(name: &str) -> Option<Pass> {
+                let name = name.trim().replace('_', "-");
+                ALL_PASSES.iter().copied().find(|p| p.name() == name)
+            }
+
+            // nivel de optimizacion desde el que corre
+            fn level(&self) -> u32 {
+                match self {
+                    Pass::DeadCode => 2,
+                    _ => 1,
+                }
+            }
+        }
+
+        pub struct PassManager {
+            level: u32,
+            overrides: HashMap<Pass, bool>,
+            print_after: Vec<Pass>,
+            time_passes: bool,
+            timings: Vec<(Pass, Duration)>,
+        }
+
+        impl PassManager {
+            pub fn new() -> PassManager {
+                PassManager {
+                    level: 1,
+                    overrides: HashMap::new(),
+                    print_after: Vec::new(),
+                    time_passes: false,
+                    timings: Vec::new(),
+                }
+            }
+
+            pub fn set_level(&mut self, level: u32) {
+                self.level = level;
+            }
+
+            pub fn set_enabled(&mut self, pass: Pass, enabled: bool) {
+                self.overrides.insert(pass, enabled);
+            }
+
+            pub fn print_after(&mut self, pass: Pass) {
+                self.print_after.push(pass);
+            }
+
+            pub fn set_time_passes(&mut self, enabled: bool) {
+                self.time_passes = enabled;
+            }
+
+            pub fn is_enabled(&self, pass: Pass) -> bool {
+                *self.overrides.get(&pass).unwrap_or(&(self.level >= pass.level()))
+            }
+
+            // Corre `pass` sobre `program` si esta activa. `dump` da el texto
+            // que se escribe en stderr con --print-after.
+            pub fn run<T>(&mut self, pass: Pass, program: &mut T, run: impl FnOnce(&mut T), dump: impl Fn(&T) -> String) {
+                if !self.is_enabled(pass) {
+                    return;
+                }
+                let start = Instant::now();
+                run(program);
+                self.timings.push((pass, start.elapsed()));
+                if self.print_after.contains(&pass) {
+                    eprintln!("*** after {} ***", pass.name());
+                    eprint!("{}", dump(program));
+                }
+            }
+
+            // Tiempo de cada pasada que corrio, con --time-passes
+            pub fn report_times(&self) {
+                if !self.time_passes {
+                    return;
+                }
+                let mut total = Duration::new(0, 0);
+                eprintln!("pass timings:");
+                for (pass, time) in &self.timings {
+                    eprintln!("  {:<10} {:>9.3} ms", pass.name(), time.as_secs_f64() * 1000.0);
+                    total += *time;
+                }
+                eprintln!("  {:<10} {:>9.3} ms", "total", total.as_secs_f64() * 1000.0);
+            }
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use super::*;
+
+            fn enabled(passes: &PassManager) -> Vec<Pass> {
+                ALL_PASSES.iter().copied().filter(|pass| passes.is_enabled(*pass)).collect()
+            }
+
+            #[test]
+            fn levels_select_the_documented_passes() {
+                let mut passes = PassManager::new();
+                assert_eq!(enabled(&passes), [Pass::Fold, Pass::RegAlloc, Pass::Peephole]);
+                passes.set_level(0);
+                assert_eq!(enabled(&passes), []);
+                passes.set_level(2);
+                assert_eq!(enabled(&passes), ALL_PASSES);
+            }
+
+            #[test]
+            fn overrides_win_over_the_level() {
+                let mut passes = PassManager::new();
+                passes.set_level(0);
+                passes.set_enabled(Pass::DeadCode, true);
+                assert_eq!(enabled(&passes), [Pass::DeadCode]);
+                passes.set_level(2);
+                passes.set_enabled(Pass::Peephole, false);
+                assert_eq!(enabled(&passes), [Pass::Fold, Pass::DeadCode, Pass::RegAlloc]);
+            }
+
+            #[test]
+            fn disabled_passes_do_not_run() {
+                let mut passes = PassManager::new();
+                let mut runs = Vec::new();
+                for pass in ALL_PASSES.iter() {
+                    passes.run(*pass, &mut runs, |runs| runs.push(*pass), |_| String::new());
+                }
+                assert_eq!(runs, [Pass::Fold, Pass::RegAlloc, Pass::Peephole]);
+                let timed: Vec<Pass> = passes.timings.iter().map(|(pass, _)| *pass).collect();
+                assert_eq!(timed, runs);
+            }
+        }
+    }
+
+    pub mod lints {
+        use super::analyzer::SymbolTable;
+        use super::cfg::Cfg;
+        use super::diagnostics::{report, Severity};
+        use super::ir::{Instr, IrProgram, Operand};
+        use super::ReferenceKind;
+        use super::StatementType;
+        use super::TreeNode;
+        use std::collections::HashMap;
+
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+        pub enum Lint {
+            UnusedVariables,
+            WriteOnlyVariables,
+            ConstantConditions,
+            EmptyBlocks,
+            ShadowedVariables,
+            UninitializedVariables,
+            UnreachableCode,
+        }
+
+        pub const ALL_LINTS: [Lint; 7] = [
+            Lint::UnusedVariables,
+            Lint::WriteOnlyVariables,
+            Lint::ConstantConditions,
+            Lint::EmptyBlocks,
+            Lint::ShadowedVariables,
+            Lint::UninitializedVariables,
+            Lint::UnreachableCode,
+        ];
+
+        impl Lint {
+            pub fn name(&self) -> &'static str {
+                match self {
+                    Lint::UnusedVariables => "unused_variables",
+                    Lint::WriteOnlyVariables => "write_only_variables",
+                    Lint::ConstantConditions => "constant_conditions",
+                    Lint::EmptyBlocks => "empty_blocks",
+                    Lint::ShadowedVariables => "shadowed_variables",
+                    Lint::UninitializedVariables => "uninitialized_variables",
+                    Lint::UnreachableCode => "unreachable_code",
+                }
+            }
 
-            fn error_msg(&mut self, label: &str, name: &str, line: u32, msg: &str) {
-                eprintln!(
-                    "error: line - {} error: {}, msg: {} for {}",
-                    line, label, msg, name
-                );
+            pub fn from_name(name: &str) -> Option<Lint> {
+                let name = name.trim().replace('-', "_");
+                ALL_LINTS.iter().copied().find(|l| l.name() == name)
             }
+        }
+
+        #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+        pub enum Level {
+            Allow,
+            Warn,
+            Deny,
+        }
 
-            fn declarationError(&mut self, name: &str, line: u32, msg: &str) {
-                self.error_msg("symbol table", name, line, msg);
+        impl Level {
+            pub fn from_name(name: &str) -> Option<Level> {
+                match name.trim() {
+                    "allow" => Some(Level::Allow),
+                    "warn" => Some(Level::Warn),
+                    "deny" => Some(Level::Deny),
+                    _ => None,
+                }
             }
+        }
 
-            pub fn lookup(&mut self, name: &str, lineno: u32) -> Option<&BucketList> {
-                if !self.table.contains_key(name) {
-                    self.declarationError(name, 0, "variable no declared");
-                } else {
-                    self.table.get_mut(name).unwrap().lines.push(lineno);
+        // Niveles de cada lint. Los pragmas del codigo fuente se aplican despues de
+        // la linea de comandos, asi que tienen prioridad sobre ella, y valen para
+        // todo el archivo sin importar la linea en que esten.
+        pub struct LintConfig {
+            levels: HashMap<Lint, Level>,
+            pub deny_warnings: bool,
+        }
+
+        impl LintConfig {
+            pub fn new() -> LintConfig {
+                LintConfig {
+                    levels: HashMap::new(),
+                    deny_warnings: false,
                 }
-                return self.table.get(name);
             }
 
-            pub fn lookup_no_decl(&mut self, name: &str) -> Option<&BucketList> {
-                if !self.table.contains_key(name) {
-                    self.declarationError(name, 0, "variable no declared");
+            pub fn set(&mut self, lint: Lint, level: Level) {
+                self.levels.insert(lint, level);
+            }
+
+            pub fn level(&self, lint: Lint) -> Level {
+                *self.levels.get(&lint).unwrap_or(&Level::Warn)
+            }
+
+            // Aplica un pragma `lint: allow(unused_variables, empty_blocks)`
+            pub fn apply_pragma(&mut self, line: u32, pragma: &str) {
+                let parsed = pragma.find('(').and_then(|open| {
+                    let close = pragma.rfind(')')?;
+                    let level = Level::from_name(&pragma[..open])?;
+                    Some((level, &pragma[open + 1..close]))
+                });
+                match parsed {
+                    Some((level, names)) => {
+                        for name in names.split(',') {
+                            match Lint::from_name(name) {
+                                Some(lint) => self.set(lint, level),
+                                None => report(
+                                    Severity::Warning,
+                                    "pragma",
+                                    line,
+                                    &format!("unknown lint '{}'", name.trim()),
+                                ),
+                            }
+                        }
+                    }
+                    None => report(
+                        Severity::Warning,
+                        "pragma",
+                        line,
+                        &format!("malformed lint pragma '{}'", pragma),
+                    ),
                 }
-                return self.table.get(name);
             }
 
-            pub fn print(&mut self) {
-                println!("TABLA DE SIMBOLOS");
-                println!("Variable Name  Location  Line Numbers  Data Type");
-                println!("*************  ********  ************  **********");
-                for (name, bucket) in &self.table {
-                    for lines in &bucket.lines {
-                        println!(
-                            "{}  {}  {}  {:?}",
-                            name, bucket.mem_location, lines, bucket.data_type
-                        );
+            pub fn emit(&self, lint: Lint, line: u32, msg: &str) {
+                match self.level(lint) {
+                    Level::Allow => {}
+                    Level::Warn if !self.deny_warnings => {
+                        report(Severity::Warning, lint.name(), line, msg)
                     }
+                    _ => report(Severity::Error, lint.name(), line, msg),
                 }
             }
         }
-    }
 
-    pub mod checker {
-        use super::analyzer::SymbolTable;
-        use super::StatementType;
-        use super::TinyType;
-        use super::TreeNode;
+        // Un bloque que solo tiene declaraciones tampoco ejecuta nada
+        fn is_empty_block(node: &TreeNode) -> bool {
+            node.statement_type == StatementType::Sequence
+                && node.nodes.iter().all(|n| {
+                    n.statement_type == StatementType::NoType
+                        || n.statement_type == StatementType::ListVariableDec
+                })
+        }
 
-        fn postProc(node: &mut TreeNode, sym_table: &mut SymbolTable) {
-            match node.statement_type {
-                StatementType::If => {
-                    if node.nodes[0].val_type != TinyType::Boolean {
-                        eprintln!(
-                            "error: line - {} error: if condition is not boolean",
-                            node.token.line
-                        );
-                    }
-                }
-                StatementType::While => {
-                    if node.nodes[0].val_type != TinyType::Boolean {
-                        eprintln!(
-                            "error: line - {} error: while condition is not boolean",
-                            node.token.line
-                        );
-                    }
+        fn check_node(node: &TreeNode, config: &LintConfig) {
+            let name = match node.statement_type {
+                StatementType::If => "if",
+                StatementType::While => "while",
+                StatementType::Repeat => "do ... until",
+                _ => "",
+            };
+            if !name.is_empty() {
+                if is_empty_block(&node.nodes[1]) {
+                    config.emit(
+                        Lint::EmptyBlocks,
+                        node.token.line,
+                        &format!("empty body in {}", name),
+                    );
                 }
-                StatementType::Repeat => {
-                    if node.nodes[0].val_type != TinyType::Boolean {
-                        eprintln!(
-                            "error: line - {} error: repeat condition is not boolean",
-                            node.token.line
-                        );
-                    }
+                if node.statement_type == StatementType::If && is_empty_block(&node.nodes[2]) {
+                    config.emit(Lint::EmptyBlocks, node.token.line, "empty else block");
                 }
-                StatementType::Not => {
-                    if node.nodes[0].val_type != TinyType::Boolean {
-                        eprintln!(
-                            "error: line - {} error: not in not a boolean value",
-                            node.token.line
-                        );
-                    }
+            }
+            for child in &node.nodes {
+                // Bloques `{ }` anidados dentro de otro bloque
+                if node.statement_type == StatementType::Sequence && is_empty_block(child) {
+                    config.emit(Lint::EmptyBlocks, child.token.line, "empty block");
                 }
+                check_node(child, config);
+            }
+        }
 
-                StatementType::BooleanExp => {
-                    if node.nodes[0].val_type == TinyType::Boolean
-                        && node.nodes[1].val_type == TinyType::Boolean {
-                            node.val_type = TinyType::Boolean;
-                    } else{
-                        eprintln!(
-                            "error: line - {} error: cannot compare {:?} to {:?} for {}",
-                            node.token.line,
-                            node.nodes[0].val_type,
-                            node.nodes[1].val_type,
-                            &node.nodes[0].token.lexema
-                        );
-                    }
+        fn assigned_var(instr: &Instr) -> Option<usize> {
+            match instr {
+                Instr::Copy { dest: Operand::Var(v), .. }
+                | Instr::Binary { dest: Operand::Var(v), .. }
+                | Instr::Cast { dest: Operand::Var(v), .. }
+                | Instr::Read { dest: Operand::Var(v), .. } => Some(*v),
+                _ => None,
+            }
+        }
+
+        fn read_vars(instr: &Instr) -> Vec<usize> {
+            let operands = match instr {
+                Instr::Copy { src, .. } | Instr::Cast { src, .. } | Instr::Write { src, .. } => vec![*src],
+                Instr::Binary { left, right, .. } | Instr::CondGoto { left, right, .. } => vec![*left, *right],
+                _ => vec![],
+            };
+            operands
+                .into_iter()
+                .filter_map(|operand| match operand {
+                    Operand::Var(v) => Some(v),
+                    _ => None,
+                })
+                .collect()
+        }
+
+        // Variables asignadas con seguridad al salir de un bloque, a partir de
+        // las asignadas al entrar
+        fn transfer(program: &IrProgram, cfg: &Cfg, block: usize, assigned: &mut [bool]) {
+            let block = &cfg.blocks[block];
+            for quad in &program.code[block.start..block.end] {
+                if let Some(v) = assigned_var(&quad.instr) {
+                    assigned[v] = true;
                 }
+            }
+        }
 
-                StatementType::Arithmetic => {
-                    if node.nodes[0].val_type != TinyType::Boolean
-                    && node.nodes[0].val_type != TinyType::NoType{
-                        if node.nodes[1].val_type != TinyType::Boolean
-                            && node.nodes[1].val_type != TinyType::NoType{
-                                node.val_type = node.nodes[0].val_type;
+        // Analisis de flujo sobre el grafo: una variable esta asignada al entrar
+        // a un bloque si lo esta al salir de todos sus predecesores
+        pub fn check_flow(program: &IrProgram, cfg: &Cfg, st: &SymbolTable, config: &LintConfig) {
+            let vars = program.vars.len();
+            let mut block_in = vec![vec![true; vars]; cfg.blocks.len()];
+            let mut block_out = vec![vec![true; vars]; cfg.blocks.len()];
+            block_in[cfg.entry()] = vec![false; vars];
+            let mut changed = true;
+            while changed {
+                changed = false;
+                for block in 0..cfg.blocks.len() {
+                    if block != cfg.entry() && !cfg.blocks[block].preds.is_empty() {
+                        let mut assigned = vec![true; vars];
+                        for &pred in &cfg.blocks[block].preds {
+                            for (v, value) in assigned.iter_mut().enumerate() {
+                                *value = *value && block_out[pred][v];
                             }
-                        else{
-                            eprintln!(
-                                "error: line - {} error: cannot do operation {:?} on {:?} for {}",
-                                node.token.line,
-                                node.nodes[0].val_type,
-                                node.val_type,
-                                &node.nodes[0].token.lexema
-                            );
                         }
+                        block_in[block] = assigned;
                     }
-                    else{
-                        eprintln!(
-                            "error: line - {} error: cannot do operation {:?} on {:?} for {}",
-                            node.token.line,
-                            node.nodes[0].val_type,
-                            node.val_type,
-                            &node.nodes[0].token.lexema
-                        );
+                    let mut assigned = block_in[block].clone();
+                    transfer(program, cfg, block, &mut assigned);
+                    if assigned != block_out[block] {
+                        block_out[block] = assigned;
+                        changed = true;
                     }
                 }
-                StatementType::Relational => {
-                    if node.nodes[0].val_type != TinyType::Boolean
-                        && node.nodes[0].val_type != TinyType::NoType{
-                            if node.nodes[1].val_type != TinyType::Boolean
-                                && node.nodes[1].val_type != TinyType::NoType{
-                                    node.val_type = TinyType::Boolean;
-                                }
-                            else{
-                                eprintln!(
-                                    "error: line - {} error: cannot compare {:?} to {:?} for {}",
-                                    node.token.line,
-                                    node.nodes[0].val_type,
-                                    node.val_type,
-                                    &node.nodes[0].token.lexema
-                                );
-                            }
-                        }
-                        else{
-                            eprintln!(
-                                "error: line - {} error: cannot compare {:?} to {:?} for {}",
-                                node.token.line,
-                                node.nodes[0].val_type,
-                                node.val_type,
-                                &node.nodes[0].token.lexema
-                            );
-                        }
-                }
-                StatementType::Assignment => {
-                    if node.nodes[0].val_type != TinyType::Boolean
-                        && node.nodes[0].val_type != TinyType::NoType
-                    {
-                        if node.nodes[1].val_type != TinyType::Boolean
-                            && node.nodes[1].val_type != TinyType::NoType
-                        {
-                            node.val_type = node.nodes[0].val_type;
-                        } else {
-                            eprintln!(
-                                "error: line - {} error: cannot assign {:?} to {:?} for {}",
-                                node.token.line,
-                                node.nodes[0].val_type,
-                                node.val_type,
-                                &node.nodes[0].token.lexema
-                            );
-                        }
-                    } else {
-                        if node.nodes[1].val_type != TinyType::Boolean {
-                            eprintln!(
-                                "error: line - {} error: cannot assign {:?} to {:?} for {}",
-                                node.token.line,
-                                node.nodes[0].val_type,
-                                node.val_type,
-                                &node.nodes[0].token.lexema
-                            );
-                        } else {
+            }
 
-                                node.val_type = node.nodes[0].val_type;
+            let mut reported: Vec<(usize, u32)> = Vec::new();
+            for (index, block) in cfg.blocks.iter().enumerate() {
+                let mut assigned = block_in[index].clone();
+                for quad in &program.code[block.start..block.end] {
+                    for v in read_vars(&quad.instr) {
+                        if assigned[v] || reported.contains(&(v, quad.line)) {
+                            continue;
                         }
+                        reported.push((v, quad.line));
+                        let var = st.get(v);
+                        let declaration = var.declaration();
+                        let column = var
+                            .references()
+                            .iter()
+                            .find(|r| r.kind == ReferenceKind::Read && r.line == quad.line)
+                            .map_or(0, |r| r.column);
+                        config.emit(
+                            Lint::UninitializedVariables,
+                            quad.line,
+                            &format!(
+                                "variable '{}' at {}:{} may be read before it is assigned (declared at {}:{})",
+                                var.name(),
+                                quad.line,
+                                column,
+                                declaration.line,
+                                declaration.column
+                            ),
+                        );
+                    }
+                    if let Some(v) = assigned_var(&quad.instr) {
+                        assigned[v] = true;
                     }
                 }
-                StatementType::Variable => {
-                    let var = sym_table.lookup_no_decl(&node.token.lexema);
-                    node.val_type = var.unwrap().data_type;
-                }
-                _ => {}
             }
         }
 
-        pub fn typeChecking(node: &mut TreeNode, sym_table: &mut SymbolTable) {
-            for child in &mut node.nodes {
-                typeChecking(child, sym_table);
+        pub fn check(program: &TreeNode, st: &SymbolTable, config: &LintConfig) {
+            for var in st.iter() {
+                let line = var.declaration().line;
+                if let Some(outer) = var.shadows() {
+                    config.emit(
+                        Lint::ShadowedVariables,
+                        line,
+                        &format!(
+                            "variable '{}' shadows the declaration on line {}",
+                            var.name(),
+                            st.get(outer).declaration().line
+                        ),
+                    );
+                }
+                if var.count(ReferenceKind::Read) > 0 {
+                    continue;
+                }
+                if var.count(ReferenceKind::Written) == 0 {
+                    config.emit(
+                        Lint::UnusedVariables,
+                        line,
+                        &format!("variable '{}' is declared but never used", var.name()),
+                    );
+                } else {
+                    config.emit(
+                        Lint::WriteOnlyVariables,
+                        line,
+                        &format!("variable '{}' is assigned but its value is never read", var.name()),
+                    );
+                }
             }
-            postProc(node, sym_table);
+            check_node(program, config);
         }
     }
 
     pub mod parser {
+        use super::diagnostics::{error, ErrorCode};
         use super::scanner::Scanner;
         use super::StatementType;
         use super::TinyType;
@@ -1340,7 +6031,7 @@ mod compiler {
         use super::TokenType;
         use super::TreeNode;
         use super::{
-            new_arithmetic, new_assignment, new_boolean_exp, new_if, new_list_dec, new_literal,
+            new_arithmetic, new_assignment, new_boolean_exp, new_cast, new_if, new_list_dec, new_literal,
             new_literal_boolean, new_program, new_read, new_relational, new_repeat, new_sequence,
             new_sequence_var, new_unary, new_var, new_while, new_write, null_token, null_tree,
         };
@@ -1360,20 +6051,21 @@ mod compiler {
             };
         }
         impl TokenParser {
-            fn error_msg(&mut self, label: &str, token: &Token, msg: &str) {
-                eprintln!(
-                    "error: line - {} error: {}, token: {:?}, msg: {}",
-                    token.line, label, token.token, msg
+            fn error_msg(&mut self, code: ErrorCode, label: &str, token: &Token, msg: &str) {
+                error(
+                    code,
+                    token.line,
+                    &format!("{}, token: {:?}, msg: {}", label, token.token, msg),
                 );
                 self._error = true;
             }
 
-            fn type_error(&mut self, token: &Token, msg: &str) {
-                self.error_msg("type", token, msg);
+            fn type_error(&mut self, code: ErrorCode, token: &Token, msg: &str) {
+                self.error_msg(code, "type", token, msg);
             }
 
-            fn syntax_error(&mut self, token: &Token, msg: &str) {
-                self.error_msg("syntax", token, msg);
+            fn syntax_error(&mut self, code: ErrorCode, token: &Token, msg: &str) {
+                self.error_msg(code, "syntax", token, msg);
             }
 
             pub fn parse(&mut self) -> &TreeNode {
@@ -1384,7 +6076,11 @@ mod compiler {
                 self.program = new_program(&program, &self.seq_declaration(), &self.seq_stmt());
                 self.match_token(&TokenType::TK_RKEY);
                 if self.current_token.token != TokenType::TK_EOF {
-                    self.syntax_error(&self.current_token.copy_token(), "Code ends before file");
+                    self.syntax_error(
+                        ErrorCode::CodeAfterProgramEnd,
+                        &self.current_token.copy_token(),
+                        "Code ends before file",
+                    );
                 }
 
                 return &self.program;
@@ -1395,7 +6091,11 @@ mod compiler {
                     let new_token = self.get_next_token();
                     self.current_token = new_token;
                 } else {
-                    self.syntax_error(&self.current_token.copy_token(), "unexpected token");
+                    self.syntax_error(
+                        ErrorCode::UnexpectedToken,
+                        &self.current_token.copy_token(),
+                        "unexpected token",
+                    );
                 }
             }
 
@@ -1403,6 +6103,14 @@ mod compiler {
                 return self.scanner.get_token();
             }
 
+            pub fn pragmas(&self) -> &Vec<(u32, String)> {
+                self.scanner.pragmas()
+            }
+
+            pub fn source_lines(&self) -> &Vec<String> {
+                self.scanner.source_lines()
+            }
+
             pub fn print_grammar_parser(&self) {
                 println!("ARBOL GRAMATICAL");
                 self.program.print_grammar_tree(1);
@@ -1413,6 +6121,12 @@ mod compiler {
                 self.program.print_syntax_tree(0);
             }
 
+            fn is_type_token(&self) -> bool {
+                self.current_token.token == TokenType::TK_INT
+                    || self.current_token.token == TokenType::TK_FLOAT
+                    || self.current_token.token == TokenType::TK_BOOL
+            }
+
             fn seq_stmt(&mut self) -> TreeNode {
                 if self.current_token.token == TokenType::TK_RKEY {
                     return new_sequence(&null_tree());
@@ -1438,16 +6152,15 @@ mod compiler {
                 return retval;
             }
 
+            // Declaraciones al inicio del programa. Las que vienen despues de la primera
+            // sentencia las lee stmt()
             fn seq_declaration(&mut self) -> TreeNode {
-                if self.current_token.token == TokenType::TK_RKEY {
+                if !self.is_type_token() {
                     return new_sequence_var(&null_tree());
                 }
 
                 let mut retval: TreeNode = new_sequence_var(&self.declaration());
-                while self.current_token.token == TokenType::TK_INT
-                    || self.current_token.token == TokenType::TK_FLOAT
-                    || self.current_token.token == TokenType::TK_BOOL
-                {
+                while self.is_type_token() {
                     let q: TreeNode = self.declaration();
                     retval.append(&q);
                 }
@@ -1478,6 +6191,7 @@ mod compiler {
                     _ => {
                         self.current_token = self.get_next_token();
                         self.syntax_error(
+                            ErrorCode::InvalidDeclaration,
                             &self.current_token.copy_token(),
                             "token in initial list variable declaration",
                         );
@@ -1498,6 +6212,7 @@ mod compiler {
                     _ => {}
                 }
                 first_var.set_type(&val_type);
+                self.initializer(&mut first_var);
                 let mut retval: TreeNode = new_list_dec(token, &first_var, val_type);
                 while self.current_token.token == TokenType::TK_COMMA {
                     self.match_token(&TokenType::TK_COMMA);
@@ -1505,14 +6220,27 @@ mod compiler {
                     self.match_token(&TokenType::TK_ID);
                     let mut _var: TreeNode = new_var(&_id);
                     _var.set_type(&val_type);
+                    self.initializer(&mut _var);
                     retval.append(&_var);
                 }
                 return retval;
             }
 
+            // `= expresion` opcional despues del nombre; queda como hijo de la variable
+            fn initializer(&mut self, variable: &mut TreeNode) {
+                if self.current_token.token == TokenType::TK_ASSIGN {
+                    self.match_token(&TokenType::TK_ASSIGN);
+                    let value: TreeNode = self.b_expression();
+                    variable.append(&value);
+                }
+            }
+
             fn stmt(&mut self) -> TreeNode {
                 let mut statement: TreeNode = null_tree();
                 match self.current_token.token {
+                    TokenType::TK_INT | TokenType::TK_FLOAT | TokenType::TK_BOOL => {
+                        statement = self.declaration();
+                    }
                     TokenType::TK_ID => {
                         statement = self.assign_stmt();
                         self.match_token(&TokenType::TK_SEMICOLON);
@@ -1536,12 +6264,16 @@ mod compiler {
                         self.match_token(&TokenType::TK_SEMICOLON);
                     }
                     TokenType::TK_LKEY => {
+                        let lkey: Token = self.current_token.copy_token();
+                        self.match_token(&TokenType::TK_LKEY);
                         statement = self.seq_stmt();
+                        statement.token = lkey;
                         self.match_token(&TokenType::TK_RKEY);
                     }
                     _ => {
                         self.current_token = self.get_next_token();
                         self.syntax_error(
+                            ErrorCode::ExpectedStatement,
                             &self.current_token.copy_token(),
                             "Code ends before file",
                         );
@@ -1564,6 +6296,7 @@ mod compiler {
                 if _var.token == TokenType::TK_ID {
                     variable = new_var(&_var);
                 }
+                variable.set_lvalue(true);
                 self.match_token(&TokenType::TK_ID);
                 return new_read(&_read, &variable);
             }
@@ -1736,8 +6469,22 @@ mod compiler {
                         self.match_token(&TokenType::TK_RPAREN);
                         return exp;
                     }
+                    TokenType::TK_INT | TokenType::TK_FLOAT => {
+                        let _cast: Token = self.current_token.copy_token();
+                        self.match_token(&_cast.token);
+                        self.match_token(&TokenType::TK_LPAREN);
+                        let exp: TreeNode = self.expression();
+                        self.match_token(&TokenType::TK_RPAREN);
+                        let val_type = if _cast.token == TokenType::TK_INT {
+                            TinyType::Integer
+                        } else {
+                            TinyType::Float
+                        };
+                        return new_cast(&_cast, &exp, val_type);
+                    }
                     _ => {
                         self.syntax_error(
+                            ErrorCode::ExpectedExpression,
                             &self.current_token.copy_token(),
                             "Code ends before file",
                         );
@@ -1754,27 +6501,555 @@ use std::env;
 
 // use crate::compiler::Token;
 use crate::compiler::analyzer;
+use crate::compiler::c_backend;
+use crate::compiler::cfg;
+use crate::compiler::deadcode;
 use crate::compiler::checker::typeChecking;
+use crate::compiler::diagnostics;
+use crate::compiler::fold;
+use crate::compiler::ir;
+use crate::compiler::lints;
+use crate::compiler::llvm_backend;
 use crate::compiler::parser;
+use crate::compiler::passes;
+use crate::compiler::regalloc;
 use crate::compiler::scanner;
 use crate::compiler::codegen;
+use crate::compiler::tm;
+use crate::compiler::wat_backend;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process;
+
+// Que se escribe en la salida estandar. `Phases` es la salida completa que lee el IDE.
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Emit {
+    Phases,
+    SymbolsJson,
+    SymbolsCsv,
+    Ir,
+    CfgDot,
+    Tmo,
+    Listing,
+}
+
+impl Emit {
+    fn from_name(name: &str) -> Option<Emit> {
+        match name {
+            "phases" => Some(Emit::Phases),
+            "symbols-json" => Some(Emit::SymbolsJson),
+            "symbols-csv" => Some(Emit::SymbolsCsv),
+            "ir" => Some(Emit::Ir),
+            "cfg-dot" => Some(Emit::CfgDot),
+            "tmo" => Some(Emit::Tmo),
+            "listing" => Some(Emit::Listing),
+            _ => None,
+        }
+    }
+}
+
+// Lenguaje del codigo generado
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Target {
+    Tm,
+    C,
+    Wat,
+    Llvm,
+}
+
+impl Target {
+    fn from_name(name: &str) -> Option<Target> {
+        match name {
+            "tm" => Some(Target::Tm),
+            "c" => Some(Target::C),
+            "wat" => Some(Target::Wat),
+            "llvm" => Some(Target::Llvm),
+            _ => None,
+        }
+    }
+}
+
+// Herramientas para programas TM que no compilan un programa fuente
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum TmTool {
+    Check,
+    Assemble,
+    Disassemble,
+}
+
+struct Options {
+    file_name: String,
+    explain: Option<String>,
+    tm_tool: Option<(TmTool, String)>,
+    emit: Emit,
+    target: Target,
+    lints: lints::LintConfig,
+    runtime_checks: bool,
+    passes: passes::PassManager,
+}
+
+fn usage_error(msg: &str) -> ! {
+    eprintln!("error: {}", msg);
+    eprintln!(
+        "usage: lexic-analyzer [--emit=<kind>] [--target=tm|c|wat|llvm] [--allow <lint>] [--warn <lint>] [--deny <lint>] [--deny-warnings] [--runtime-checks] [-O0|-O1|-O2] [--enable-pass=<pass>] [--disable-pass=<pass>] [--print-after=<pass>] [--time-passes] <filename>"
+    );
+    eprintln!("       kinds: phases, symbols-json, symbols-csv, ir, cfg-dot, tmo, listing");
+    eprintln!("       passes: fold, dead-code, regalloc, peephole");
+    eprintln!("       llvm: LLVM 15 or later (LLVM 14 with -opaque-pointers)");
+    eprintln!("       lexic-analyzer --explain <E0xxx>");
+    eprintln!("       lexic-analyzer --check-tm <file.tm>");
+    eprintln!("       lexic-analyzer --assemble <file.tm> > <file.tmo>");
+    eprintln!("       lexic-analyzer --disassemble <file.tmo>");
+    process::exit(1);
+}
+
+fn pass_arg(name: &str) -> passes::Pass {
+    match passes::Pass::from_name(name) {
+        Some(pass) => pass,
+        None => usage_error(&format!("unknown pass '{}'", name)),
+    }
+}
+
+fn parse_args(args: &[String]) -> Options {
+    let mut options = Options {
+        file_name: String::new(),
+        explain: None,
+        tm_tool: None,
+        emit: Emit::Phases,
+        target: Target::Tm,
+        lints: lints::LintConfig::new(),
+        runtime_checks: false,
+        passes: passes::PassManager::new(),
+    };
+    let mut i = 1;
+    while i < args.len() {
+        let arg = args[i].as_str();
+        match arg {
+            "--explain" | "--check-tm" | "--assemble" | "--disassemble" | "--allow" | "--warn" | "--deny" => {
+                i += 1;
+                let value = match args.get(i) {
+                    Some(value) => value,
+                    None => usage_error(&format!("missing value for {}", arg)),
+                };
+                if arg == "--explain" {
+                    options.explain = Some(value.clone());
+                } else if arg == "--check-tm" {
+                    options.tm_tool = Some((TmTool::Check, value.clone()));
+                } else if arg == "--assemble" {
+                    options.tm_tool = Some((TmTool::Assemble, value.clone()));
+                } else if arg == "--disassemble" {
+                    options.tm_tool = Some((TmTool::Disassemble, value.clone()));
+                } else {
+                    let level = lints::Level::from_name(&arg[2..]).unwrap();
+                    match lints::Lint::from_name(value) {
+                        Some(lint) => options.lints.set(lint, level),
+                        None => usage_error(&format!("unknown lint '{}'", value)),
+                    }
+                }
+            }
+            "--deny-warnings" => options.lints.deny_warnings = true,
+            "--runtime-checks" => options.runtime_checks = true,
+            "-O0" => options.passes.set_level(0),
+            "-O1" => options.passes.set_level(1),
+            "-O" | "-O2" => options.passes.set_level(2),
+            "--time-passes" => options.passes.set_time_passes(true),
+            _ if arg.starts_with("--enable-pass=") => options.passes.set_enabled(pass_arg(&arg[14..]), true),
+            _ if arg.starts_with("--disable-pass=") => options.passes.set_enabled(pass_arg(&arg[15..]), false),
+            _ if arg.starts_with("--print-after=") => options.passes.print_after(pass_arg(&arg[14..])),
+            _ if arg.starts_with("--emit=") => match Emit::from_name(&arg[7..]) {
+                Some(emit) => options.emit = emit,
+                None => usage_error(&format!("unknown output kind '{}'", &arg[7..])),
+            },
+            _ if arg.starts_with("--target=") => match Target::from_name(&arg[9..]) {
+                Some(target) => options.target = target,
+                None => usage_error(&format!("unknown target '{}'", &arg[9..])),
+            },
+            _ if arg.starts_with("--") => usage_error(&format!("unknown option '{}'", arg)),
+            _ => options.file_name = String::from(arg),
+        }
+        i += 1;
+    }
+    if options.target != Target::Tm && (options.emit == Emit::Tmo || options.emit == Emit::Listing) {
+        usage_error("--emit=tmo and --emit=listing need --target=tm");
+    }
+    options
+}
+
+fn fail(msg: &str) -> ! {
+    eprintln!("error: {}", msg);
+    process::exit(1);
+}
+
+fn write_object(object: &tm::TmObject) {
+    if let Err(err) = io::stdout().write_all(&object.to_bytes()) {
+        fail(&format!("cannot write TM object: {}", err));
+    }
+}
+
+fn validate_tm(file_name: &str, program: &[tm::TmInstruction]) {
+    let errors = tm::validate(program);
+    for err in &errors {
+        eprintln!("error: {}: instruction {}", file_name, err);
+    }
+    if !errors.is_empty() {
+        process::exit(1);
+    }
+}
+
+// Lee un programa TM, lo valida y lo vuelve a escribir sin comentarios, o
+// lo convierte entre texto y objeto binario
+fn run_tm_tool(tool: TmTool, file_name: &str) {
+    if tool == TmTool::Disassemble {
+        let bytes = fs::read(file_name).unwrap_or_else(|err| fail(&format!("cannot read '{}': {}", file_name, err)));
+        let object = tm::TmObject::from_bytes(&bytes).unwrap_or_else(|err| fail(&format!("{}: {}", file_name, err)));
+        validate_tm(file_name, &object.code);
+        print!("{}", object.to_text());
+        return;
+    }
+    let text = fs::read_to_string(file_name).unwrap_or_else(|err| fail(&format!("cannot read '{}': {}", file_name, err)));
+    let program = tm::parse_program(&text).unwrap_or_else(|err| fail(&format!("{}:{}", file_name, err)));
+    validate_tm(file_name, &program);
+    if tool == TmTool::Assemble {
+        write_object(&tm::TmObject {
+            data_size: tm::data_size(&program),
+            code: program,
+            source_map: None,
+        });
+    } else {
+        print!("{}", tm::program_text(&program));
+    }
+}
 
 fn main() {
     let args: Vec<String> = env::args().collect();
+    let mut options = parse_args(&args);
+
+    if let Some(code) = &options.explain {
+        match diagnostics::explain(code) {
+            Some(text) => print!("{}", text),
+            None => {
+                eprintln!("error: no explanation for code '{}'", code);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+    if let Some((tool, file_name)) = &options.tm_tool {
+        run_tm_tool(*tool, file_name);
+        return;
+    }
+    if options.file_name.is_empty() {
+        usage_error("no input file");
+    }
 
-    let mut scanner = scanner::Scanner::new(&args[args.len() - 1], true);
+    let tm_phases = options.emit == Emit::Phases && options.target == Target::Tm;
+    let mut scanner = scanner::Scanner::new(&options.file_name, tm_phases);
+    scanner.set_echo_source(options.emit == Emit::Listing);
 
     let mut parser: parser::TokenParser = parser::new(scanner);
     parser.parse();
     let mut symbol_table: analyzer::SymbolTable = analyzer::SymbolTable::new();
-    symbol_table.build_table(&parser.program);
+    symbol_table.build_table(&mut parser.program);
 
     typeChecking(&mut parser.program, &mut symbol_table);
 
-    let mut code_gen: codegen::CodeGenResult = codegen::CodeGenResult::new();
+    for (line, pragma) in parser.pragmas() {
+        options.lints.apply_pragma(*line, pragma);
+    }
+    lints::check(&parser.program, &symbol_table, &options.lints);
+
+    // Los avisos no dependen del nivel de optimizacion: siempre se revisan
+    // sobre el arbol evaluado
+    let mut program = parser.program.copy();
+    fold::fold(&mut program, true);
+    deadcode::check_conditions(&program, &options.lints);
+    let ir_program = ir::lower(&program, &symbol_table);
+    let graph = cfg::build(&ir_program);
+    lints::check_flow(&ir_program, &graph, &symbol_table, &options.lints);
+    deadcode::check_unreachable(&ir_program, &graph, &options.lints);
+
+    let passes = &mut options.passes;
+    let mut program = parser.program.copy();
+    passes.run(passes::Pass::Fold, &mut program, |tree| fold::fold(tree, false), |tree| {
+        tree.syntax_tree_text(0)
+    });
+    let mut ir_program = ir::lower(&program, &symbol_table);
+    passes.run(
+        passes::Pass::DeadCode,
+        &mut ir_program,
+        |ir_program| {
+            let graph = cfg::build(ir_program);
+            deadcode::remove_unreachable(ir_program, &graph);
+        },
+        |ir_program| ir_program.to_text(),
+    );
+    let graph = cfg::build(&ir_program);
+
+    match options.emit {
+        Emit::Phases if options.target == Target::C => {
+            print!("{}", c_backend::generate(&program, &symbol_table, options.runtime_checks));
+        }
+        Emit::Phases if options.target == Target::Wat => {
+            print!("{}", wat_backend::generate(&program, &symbol_table, options.runtime_checks));
+        }
+        Emit::Phases if options.target == Target::Llvm => {
+            let source_name = Path::new(&options.file_name).file_name().map_or(String::new(), |name| {
+                name.to_string_lossy().into_owned()
+            });
+            print!(
+                "{}",
+                llvm_backend::generate(&program, &symbol_table, &source_name, options.runtime_checks)
+            );
+        }
+        Emit::Phases | Emit::Tmo | Emit::Listing => {
+            let mut registers = Vec::new();
+            passes.run(
+                passes::Pass::RegAlloc,
+                &mut registers,
+                |registers| *registers = regalloc::linear_scan(&ir_program),
+                |registers| regalloc::to_text(&ir_program, registers),
+            );
+            let mut code_gen: codegen::CodeGenResult = codegen::CodeGenResult::new();
+            code_gen.set_runtime_checks(options.runtime_checks);
+            code_gen.set_registers(registers);
+            code_gen.generate(&ir_program);
+            passes.run(passes::Pass::Peephole, &mut code_gen, |code_gen| code_gen.peephole(), |code_gen| {
+                code_gen.to_text()
+            });
+
+            if options.emit == Emit::Tmo {
+                write_object(&code_gen.to_object());
+            } else if options.emit == Emit::Listing {
+                println!("TINY LISTING: {}", options.file_name);
+                print!("{}", code_gen.listing(parser.source_lines()));
+                println!();
+                print!("{}", symbol_table.to_text());
+            } else {
+                parser.print_grammar_parser();
+                parser.print_syntax_parser();
+                symbol_table.print();
+                print!("{}", code_gen.to_text());
+            }
+        }
+        Emit::Ir => print!("{}", ir_program.to_text()),
+        Emit::CfgDot => print!("{}", graph.to_dot(&ir_program)),
+        Emit::SymbolsJson => print!("{}", symbol_table.to_json()),
+        Emit::SymbolsCsv => print!("{}", symbol_table.to_csv()),
+    }
+    passes.report_times();
+
+    if diagnostics::error_count() > 0 {
+        process::exit(1);
+    }
+}
+
+// Los mismos ejemplos que usan las pruebas de tests/
+#[cfg(test)]
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Codigo intermedio de `file_name` despues de fold, como en main
+    pub fn lower(file_name: &str) -> ir::IrProgram {
+        let mut parser = parser::new(scanner::Scanner::new(file_name, false));
+        parser.parse();
+        let mut symbol_table = analyzer::SymbolTable::new();
+        symbol_table.build_table(&mut parser.program);
+        typeChecking(&mut parser.program, &mut symbol_table);
+        let mut program = parser.program.copy();
+        fold::fold(&mut program, false);
+        ir::lower(&program, &symbol_table)
+    }
+
+    // Codigo TM sin peephole; sin `regalloc` los temporales quedan en memoria
+    pub fn code_gen(file_name: &str, regalloc: bool, runtime_checks: bool) -> codegen::CodeGenResult {
+        let ir_program = lower(file_name);
+        let mut code_gen = codegen::CodeGenResult::new();
+        code_gen.set_runtime_checks(runtime_checks);
+        if regalloc {
+            code_gen.set_registers(regalloc::linear_scan(&ir_program));
+        }
+        code_gen.generate(&ir_program);
+        code_gen
+    }
+
+    // Compila `file_name` a TM como main, con las pasadas por omision
+    pub fn compile_tm(file_name: &str, runtime_checks: bool) -> Vec<tm::TmInstruction> {
+        let mut code_gen = code_gen(file_name, true, runtime_checks);
+        code_gen.peephole();
+        code_gen.to_object().code
+    }
+
+    // Compila `file_name` a TM como main, con las pasadas del nivel `level`
+    pub fn compile_at_level(file_name: &str, level: u32, runtime_checks: bool) -> Vec<tm::TmInstruction> {
+        let mut passes = passes::PassManager::new();
+        passes.set_level(level);
+        let mut parser = parser::new(scanner::Scanner::new(file_name, false));
+        parser.parse();
+        let mut symbol_table = analyzer::SymbolTable::new();
+        symbol_table.build_table(&mut parser.program);
+        typeChecking(&mut parser.program, &mut symbol_table);
 
-    parser.print_grammar_parser();
-    parser.print_syntax_parser();
-    symbol_table.print();
-    code_gen.code_gen(&parser.program, &mut symbol_table);
+        let mut program = parser.program.copy();
+        passes.run(passes::Pass::Fold, &mut program, |tree| fold::fold(tree, false), |_| String::new());
+        let mut ir_program = ir::lower(&program, &symbol_table);
+        passes.run(
+            passes::Pass::DeadCode,
+            &mut ir_program,
+            |ir_program| {
+                let graph = cfg::build(ir_program);
+                deadcode::remove_unreachable(ir_program, &graph);
+            },
+            |_| String::new(),
+        );
+        let mut registers = Vec::new();
+        passes.run(
+            passes::Pass::RegAlloc,
+            &mut registers,
+            |registers| *registers = regalloc::linear_scan(&ir_program),
+            |_| String::new(),
+        );
+        let mut code_gen = codegen::CodeGenResult::new();
+        code_gen.set_runtime_checks(runtime_checks);
+        code_gen.set_registers(registers);
+        code_gen.generate(&ir_program);
+        passes.run(passes::Pass::Peephole, &mut code_gen, |code_gen| code_gen.peephole(), |_| String::new());
+        code_gen.to_object().code
+    }
+
+    // Escribe `source` en un archivo temporal y lo traduce a codigo intermedio
+    pub fn lower_source(name: &str, source: &str) -> ir::IrProgram {
+        let path = env::temp_dir().join(format!("tiny-unit-{}-{}.tny", process::id(), name));
+        fs::write(&path, source).unwrap();
+        let program = lower(path.to_str().unwrap());
+        fs::remove_file(&path).unwrap();
+        program
+    }
+
+    // Escribe `source` en un archivo temporal y lo compila
+    pub fn compile_source(name: &str, source: &str, runtime_checks: bool) -> Vec<tm::TmInstruction> {
+        let path = env::temp_dir().join(format!("tiny-unit-{}-{}.tny", process::id(), name));
+        fs::write(&path, source).unwrap();
+        let code = compile_tm(path.to_str().unwrap(), runtime_checks);
+        fs::remove_file(&path).unwrap();
+        code
+    }
+
+    #[test]
+    fn float_sample_runs_on_tm() {
+        let code = compile_tm(common::sample("test_float").path.to_str().unwrap(), false);
+        for op in ["FADD", "FDIV", "FMUL", "ITOF", "FTOI", "FIN", "FOUT"].iter() {
+            assert!(code.iter().any(|instr| instr.op() == *op), "no {} in the program", op);
+        }
+        assert_eq!(tm::run(&code, "3 1.5 2.5 4.25"), "2.75\n2\n5\n");
+        assert_eq!(tm::run(&code, "2 1 2"), "1.5\n1\n0.5\n");
+    }
+
+    #[test]
+    fn large_float_constants_are_exact() {
+        let source = "program {
+    float x;
+    x = 3000000000.5;
+    write (x - 3000000000.0) * 4.0;
+    write (0.0 - 12345678901.25) + 12345678900.0;
+    write 3.14159265358979 * 100000.0;
+}
+";
+        let code = compile_source("large_float", source, false);
+        assert_eq!(tm::run(&code, ""), "2\n-1.25\n314159\n");
+    }
+
+    #[test]
+    fn uninitialized_block_locals_start_at_zero() {
+        // `b` no puede usar la localidad que dejo libre `a`
+        let source = "program {
+    int i;
+    {
+        int a;
+        a = 5;
+        write a;
+    }
+    i = 0;
+    while (i < 4) {
+        int b;
+        write b;
+        b = b + 1;
+        i = i + 1;
+    }
+}
+";
+        let code = compile_source("block_locals", source, false);
+        assert_eq!(tm::run(&code, ""), "5\n0\n1\n2\n3\n");
+    }
+
+    #[test]
+    fn division_by_zero_traps() {
+        let code = compile_source("division", common::DIVISION, true);
+        for (input, expected, _) in common::DIVISION_RUNS.iter() {
+            assert_eq!(tm::run(&code, input), *expected, "{}", input);
+        }
+    }
+
+    // fold deja las operaciones con variables, asi que las trampas siguen
+    #[test]
+    fn folding_keeps_runtime_checks() {
+        let source = "program {
+    int a, b;
+    read a;
+    read b;
+    write a / (b * (4 - 3));
+    write a * (1000 * 1000);
+}
+";
+        let code = compile_source("folded_checks", source, true);
+        assert_eq!(tm::run(&code, "6 3"), "2\n6000000\n");
+        assert_eq!(tm::run(&code, "6 0"), "1\n5\n");
+        assert_eq!(tm::run(&code, "5000 1"), "5000\n2\n6\n");
+    }
+
+    // Las pasadas no cambian lo que hace el programa
+    #[test]
+    fn every_level_gives_the_same_output() {
+        for (sample, expected) in common::terminating() {
+            for level in 0..3 {
+                let code = compile_at_level(sample.path.to_str().unwrap(), level, false);
+                assert_eq!(tm::run(&code, &sample.input), expected, "{} at -O{}", sample.name, level);
+            }
+        }
+        let path = env::temp_dir().join(format!("tiny-unit-{}-levels.tny", process::id()));
+        fs::write(&path, common::DIVISION).unwrap();
+        for level in 0..3 {
+            let code = compile_at_level(path.to_str().unwrap(), level, true);
+            for (input, expected, _) in common::DIVISION_RUNS.iter() {
+                assert_eq!(tm::run(&code, input), *expected, "{} at -O{}", input, level);
+            }
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    // Salida de printf("%g") de C para los mismos valores
+    #[test]
+    fn format_g_matches_printf() {
+        let cases = [
+            (0.0, "0"),
+            (2.75, "2.75"),
+            (11.0 / 12.0, "0.916667"),
+            (1e6, "1e+06"),
+            (999999.5, "1e+06"),
+            (123456.0, "123456"),
+            (0.0001, "0.0001"),
+            (0.00001234, "1.234e-05"),
+            (-1.25, "-1.25"),
+            (1.0 / 3.0, "0.333333"),
+            (3e100, "3e+100"),
+            (-0.000099999999, "-0.0001"),
+        ];
+        for (value, expected) in cases.iter() {
+            assert_eq!(common::format_g(*value), *expected, "{}", value);
+        }
+    }
 }
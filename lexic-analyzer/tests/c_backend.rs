@@ -0,0 +1,132 @@
+// Compila los ejemplos .tny con --target=c, compila el resultado con cc y
+// compara lo que escribe el programa con la salida esperada.
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{self, Command, Stdio};
+
+mod common;
+
+fn has_cc() -> bool {
+    Command::new("cc").arg("--version").output().is_ok()
+}
+
+// Salida del programa y si termino con un estado de error
+fn compile_and_run(sample: &Path, input: &str, flags: &[&str]) -> (String, bool) {
+    let output = Command::new(env!("CARGO_BIN_EXE_lexic-analyzer"))
+        .args(flags)
+        .arg("--target=c")
+        .arg(sample)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{} did not compile", sample.display());
+
+    let name = sample.file_stem().unwrap().to_str().unwrap();
+    let dir = env::temp_dir().join(format!("tiny-c-{}-{}", process::id(), name));
+    fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("program.c");
+    let exe = dir.join("program");
+    fs::write(&source, &output.stdout).unwrap();
+    let cc = Command::new("cc")
+        .args(["-std=c99", "-Wall", "-Wextra", "-pedantic", "-Werror", "-o"])
+        .arg(&exe)
+        .arg(&source)
+        .output()
+        .unwrap();
+    assert!(cc.status.success(), "cc failed for {}:\n{}", sample.display(), String::from_utf8_lossy(&cc.stderr));
+
+    let mut child = Command::new(&exe).stdin(Stdio::piped()).stdout(Stdio::piped()).spawn().unwrap();
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    let run = child.wait_with_output().unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    (String::from_utf8(run.stdout).unwrap(), !run.status.success())
+}
+
+#[test]
+fn samples_match_expected_output() {
+    if !has_cc() {
+        eprintln!("cc not found, skipping");
+        return;
+    }
+    for (sample, expected) in common::terminating() {
+        assert_eq!(compile_and_run(&sample.path, &sample.input, &[]), (expected, false), "{}", sample.name);
+    }
+}
+
+// Nombres de macros de los encabezados y de las funciones del programa C,
+// un nombre repetido cuyo nuevo nombre ya existe y una suma que se desborda
+const NAMES: &str = "program {
+    int EOF, NULL, INT_MAX, stdin, checked_add, trap, wrap_add, x_9, x;
+    EOF = 1;
+    NULL = 2;
+    INT_MAX = 3;
+    stdin = 4;
+    checked_add = 5;
+    trap = 6;
+    wrap_add = 7;
+    x_9 = 8;
+    do {
+        int x;
+        x = (EOF + NULL) + (INT_MAX + stdin);
+        write x + ((checked_add + trap) + (wrap_add + x_9));
+    } until (true);
+    write x;
+    stdin = 2147483647;
+    write stdin + 1;
+}
+";
+
+#[test]
+fn reserved_names_and_overflow() {
+    if !has_cc() {
+        eprintln!("cc not found, skipping");
+        return;
+    }
+    let path = env::temp_dir().join(format!("tiny-c-{}-names.tny", process::id()));
+    fs::write(&path, NAMES).unwrap();
+    assert_eq!(compile_and_run(&path, "", &[]), (String::from("36\n0\n-2147483648\n"), false));
+    assert_eq!(compile_and_run(&path, "", &["--runtime-checks"]), (String::from("36\n0\n2\n18\n"), true));
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn runtime_checks_do_not_change_output() {
+    if !has_cc() {
+        eprintln!("cc not found, skipping");
+        return;
+    }
+    for (sample, expected) in common::terminating() {
+        let result = compile_and_run(&sample.path, &sample.input, &["--runtime-checks"]);
+        assert_eq!(result, (expected, false), "{}", sample.name);
+    }
+}
+
+#[test]
+fn division_by_zero_traps() {
+    if !has_cc() {
+        eprintln!("cc not found, skipping");
+        return;
+    }
+    let path = env::temp_dir().join(format!("tiny-c-{}-division.tny", process::id()));
+    fs::write(&path, common::DIVISION).unwrap();
+    for (input, expected, trapped) in common::DIVISION_RUNS.iter() {
+        let result = compile_and_run(&path, input, &["--runtime-checks"]);
+        assert_eq!(result, (String::from(*expected), *trapped), "{}", input);
+    }
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn failed_reads_give_zero() {
+    if !has_cc() {
+        eprintln!("cc not found, skipping");
+        return;
+    }
+    let path = env::temp_dir().join(format!("tiny-c-{}-reads.tny", process::id()));
+    fs::write(&path, common::FAILED_READS).unwrap();
+    for (input, expected) in common::FAILED_READS_RUNS.iter() {
+        assert_eq!(compile_and_run(&path, input, &[]), (String::from(*expected), false), "{}", input);
+    }
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,115 @@
+// Ejemplos de tests/samples que usan todas las pruebas. Cada `nombre.tny`
+// puede tener su entrada en `nombre.in` y la salida esperada en
+// `nombre.out`; un ejemplo sin `.out` no termina y solo se compila.
+#![allow(dead_code)]
+use std::fs;
+use std::path::PathBuf;
+
+pub struct Sample {
+    pub name: String,
+    pub path: PathBuf,
+    pub input: String,
+    pub output: Option<String>,
+}
+
+impl Sample {
+    // Ruta relativa al crate, para salidas que la incluyen
+    pub fn relative_path(&self) -> String {
+        format!("tests/samples/{}.tny", self.name)
+    }
+}
+
+// Ejemplos en orden de nombre
+pub fn samples() -> Vec<Sample> {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/samples");
+    let mut samples: Vec<Sample> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "tny"))
+        .map(|path| Sample {
+            name: String::from(path.file_stem().unwrap().to_str().unwrap()),
+            input: fs::read_to_string(path.with_extension("in")).unwrap_or_default(),
+            output: fs::read_to_string(path.with_extension("out")).ok(),
+            path,
+        })
+        .collect();
+    samples.sort_by(|a, b| a.name.cmp(&b.name));
+    samples
+}
+
+// Ejemplos que terminan, con su salida esperada
+pub fn terminating() -> Vec<(Sample, String)> {
+    samples()
+        .into_iter()
+        .filter_map(|mut sample| sample.output.take().map(|output| (sample, output)))
+        .collect()
+}
+
+pub fn sample(name: &str) -> Sample {
+    samples().into_iter().find(|sample| sample.name == name).unwrap()
+}
+
+// printf("%g"), como escriben los flotantes el programa C, el de LLVM y TM:
+// 6 cifras significativas sin ceros al final, con exponente si el exponente
+// del valor ya redondeado es menor a -4 o mayor a 5
+pub fn format_g(value: f64) -> String {
+    if value == 0.0 {
+        return String::from("0");
+    }
+    let text = format!("{:.5e}", value);
+    let (mantissa, exponent) = text.split_at(text.find('e').unwrap());
+    let exponent: i32 = exponent[1..].parse().unwrap();
+    if !(-4..6).contains(&exponent) {
+        let mantissa = mantissa.trim_end_matches('0').trim_end_matches('.');
+        let sign = if exponent < 0 { '-' } else { '+' };
+        return format!("{}e{}{:02}", mantissa, sign, exponent.abs());
+    }
+    let text = format!("{:.*}", (5 - exponent) as usize, value);
+    if text.contains('.') {
+        String::from(text.trim_end_matches('0').trim_end_matches('.'))
+    } else {
+        text
+    }
+}
+
+// Division flotante (linea 8) y entera (linea 9) entre valores que se leen.
+// Para cada entrada, la salida esperada con --runtime-checks y si el
+// programa se detiene en una trampa.
+pub const DIVISION: &str = "program {
+    int a, b;
+    float x, y;
+    read a;
+    read b;
+    read x;
+    read y;
+    write x / y;
+    write a / b;
+    write 0;
+}
+";
+
+pub const DIVISION_RUNS: [(&str, &str, bool); 3] = [
+    ("6 3 1.5 0.5", "3\n2\n0\n", false),
+    ("6 3 1.5 0", "1\n8\n", true),
+    ("6 0 1.5 0.5", "3\n1\n9\n", true),
+];
+
+// Lecturas que fallan (fin de la entrada o un valor que no es un numero):
+// la variable queda en 0 en todos los lenguajes de salida
+pub const FAILED_READS: &str = "program {
+    int a;
+    float x;
+    bool b;
+    a = 5;
+    x = 2.5;
+    b = true;
+    read a;
+    read x;
+    read b;
+    write a;
+    write x;
+    write b;
+}
+";
+
+pub const FAILED_READS_RUNS: [(&str, &str); 3] = [("7 1.5 1", "7\n1.5\n1\n"), ("", "0\n0\n0\n"), ("7 abc", "7\n0\n0\n")];
@@ -0,0 +1,212 @@
+// Compila programas pequenos y revisa los errores que reporta el compilador.
+use std::env;
+use std::fs;
+use std::process::{self, Command, Output};
+
+fn compile(name: &str, source: &str) -> Output {
+    let path = env::temp_dir().join(format!("tiny-diag-{}-{}.tny", process::id(), name));
+    fs::write(&path, source).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_lexic-analyzer")).arg(&path).output().unwrap();
+    fs::remove_file(&path).unwrap();
+    output
+}
+
+fn assert_error(name: &str, source: &str, code: &str, line: u32) {
+    let output = compile(name, source);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(!output.status.success(), "{} was accepted", name);
+    assert!(
+        stderr.contains(&format!("error[{}]: line - {} ", code, line)),
+        "{}: expected {} on line {}, got:\n{}",
+        name,
+        code,
+        line,
+        stderr
+    );
+}
+
+#[test]
+fn redeclaration_after_a_statement_is_an_error() {
+    assert_error("redeclared", "program {\n int x;\n x = 1;\n int x;\n}\n", "E0002", 4);
+}
+
+#[test]
+fn redeclaration_in_a_nested_block_shadows() {
+    let source = "program {\n int x;\n x = 1;\n do {\n  int x;\n  x = 2;\n  write x;\n } until (true);\n write x;\n}\n";
+    let output = compile("shadowed", source);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(output.status.success(), "{}", stderr);
+    assert!(stderr.contains("warning[shadowed_variables]: line - 5 "), "{}", stderr);
+}
+
+#[test]
+fn integer_literal_out_of_range_is_an_error() {
+    assert_error("large_int", "program {\n int x;\n x = 3000000000;\n write x;\n}\n", "E0020", 3);
+    let output = compile("max_int", "program {\n int x;\n x = 2147483647;\n write x;\n}\n");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+fn explain(code: &str) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_lexic-analyzer")).args(["--explain", code]).output().unwrap()
+}
+
+#[test]
+fn explain_prints_every_code() {
+    // el catalogo completo se revisa en diagnostics::tests
+    for number in 1..=20 {
+        let code = format!("E{:04}", number);
+        let output = explain(&code);
+        assert!(output.status.success(), "{}", code);
+        let text = String::from_utf8(output.stdout).unwrap();
+        assert!(text.starts_with(&format!("{}: ", code)), "{}", text);
+        assert!(text.contains("Erroneous example:"), "{}", code);
+    }
+    assert!(explain("e0001").status.success());
+}
+
+#[test]
+fn explain_rejects_unknown_codes() {
+    for code in ["E9999", "E0000", "foo"].iter() {
+        let output = explain(code);
+        assert_eq!(output.status.code(), Some(1), "{}", code);
+        assert!(output.stdout.is_empty(), "{}", code);
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert_eq!(stderr, format!("error: no explanation for code '{}'\n", code));
+    }
+}
+
+#[test]
+fn errors_show_their_code() {
+    let cases = [
+        ("undeclared", "program {\n int x;\n y = 1;\n}\n", "E0001", 3),
+        ("if", "program {\n int x;\n if (x) then {\n  x = 1;\n } fi\n}\n", "E0003", 3),
+        ("while", "program {\n int x;\n while (x) {\n  x = 1;\n }\n}\n", "E0004", 3),
+        ("until", "program {\n int x;\n do {\n  x = 1;\n } until (x);\n}\n", "E0005", 3),
+        ("not", "program {\n int x;\n bool b;\n b = not x;\n write b;\n}\n", "E0006", 4),
+        ("and", "program {\n int x;\n bool b;\n b = b and x;\n write b;\n}\n", "E0007", 4),
+        ("arithmetic", "program {\n int x;\n bool b;\n x = b * 2;\n}\n", "E0008", 4),
+        ("comparison", "program {\n bool b;\n b = b < 1;\n write b;\n}\n", "E0009", 3),
+        ("assignment", "program {\n int x;\n x = true;\n}\n", "E0010", 3),
+        ("unexpected", "program {\n int x;\n x = 1;\n if x then {\n } fi\n}\n", "E0011", 4),
+        ("statement", "program {\n int x;\n x = 1;\n 5;\n}\n", "E0013", 4),
+        ("expression", "program {\n int x;\n x = ;\n}\n", "E0014", 3),
+        ("after_end", "program {\n int x;\n}\nx = 1;\n", "E0015", 4),
+        ("before_declaration", "program {\n x = 1;\n int x;\n}\n", "E0016", 2),
+    ];
+    for (name, source, code, line) in cases.iter() {
+        assert_error(name, source, code, *line);
+    }
+}
+
+#[test]
+fn undeclared_variable_is_reported_once() {
+    // sin la variable no hay tipo y las expresiones que la usan no se revisan
+    let source = "program {
+    int x;
+    bool b;
+    y = 1;
+    x = y + 1;
+    x = int(y);
+    b = not y;
+    if (y > 0) then {
+        write x;
+    } fi
+    int z = y * 2;
+    write b;
+    write z;
+}
+";
+    let output = compile("undeclared_once", source);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let errors: Vec<&str> = stderr.lines().filter(|line| line.starts_with("error")).collect();
+    assert_eq!(errors.len(), 6, "{}", stderr);
+    assert!(errors.iter().all(|line| line.starts_with("error[E0001]")), "{}", stderr);
+}
+
+#[test]
+fn int_values_widen_to_float() {
+    // tests/samples/widening.tny revisa los valores en cada lenguaje de salida
+    let source = "program {
+    int x;
+    float f = x;
+    f = x;
+    f = x + f;
+    f = (x * 2) / f;
+    f = float(x);
+    f = float(f);
+    x = int(f);
+    x = int(x);
+    int y = int(f * 2);
+    bool b = x < f;
+    write y;
+    write b;
+}
+";
+    let output = compile("widening", source);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(output.status.success(), "{}", stderr);
+    assert!(!stderr.contains("error"), "{}", stderr);
+}
+
+#[test]
+fn float_values_need_a_cast_to_be_stored_in_an_int() {
+    let cases = [
+        ("assign_float", "program {\n int x;\n float f;\n x = f;\n}\n", 4),
+        ("init_float", "program {\n float f;\n int x = f;\n write x;\n}\n", 3),
+        ("mixed", "program {\n int x;\n float f;\n x = x + f;\n}\n", 4),
+        ("float_literal", "program {\n int x;\n x = x * 0.5;\n}\n", 3),
+    ];
+    for (name, source, line) in cases.iter() {
+        assert_error(name, source, "E0017", *line);
+    }
+
+    // un bool no es un float que se pierde: es otro error
+    let output = compile("assign_bool", "program {\n float f;\n f = true;\n}\n");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("error[E0010]: line - 3 "), "{}", stderr);
+    assert!(!stderr.contains("E0017"), "{}", stderr);
+}
+
+#[test]
+fn casts_need_a_numeric_operand() {
+    let cases = [
+        ("int_of_bool", "program {\n int x;\n bool b;\n x = int(b);\n}\n", 4),
+        ("float_of_comparison", "program {\n int x;\n float f;\n f = float(x < 1);\n}\n", 4),
+    ];
+    for (name, source, line) in cases.iter() {
+        assert_error(name, source, "E0018", *line);
+    }
+}
+
+#[test]
+fn constant_arithmetic_that_always_fails_is_an_error() {
+    let cases = [
+        ("divide_by_zero", "program {\n int x;\n x = x / 0;\n}\n", 3),
+        ("divide_by_folded_zero", "program {\n int x;\n x = x / (3 - 3);\n}\n", 3),
+        ("float_divide_by_zero", "program {\n float f;\n f = f / (0.5 - 0.5);\n}\n", 3),
+        ("add_overflow", "program {\n int x;\n x = 2147483647 + 1;\n}\n", 3),
+        ("mul_overflow", "program {\n int x;\n x = (65536 * 65536) / 2;\n}\n", 3),
+        ("folded_overflow", "program {\n bool b;\n b = (2147483647 - (0 - 1)) > 0;\n}\n", 3),
+    ];
+    for (name, source, line) in cases.iter() {
+        assert_error(name, source, "E0019", *line);
+    }
+
+    // lo que depende de una variable se revisa al ejecutar (--runtime-checks)
+    let source = "program {
+    int x;
+    float f;
+    x = x / 2;
+    x = 2147483647 + 0;
+    x = x * 65536;
+    x = (x * 65536) * 65536;
+    f = 0.5 / 0.25;
+    write x;
+    write f;
+}
+";
+    let output = compile("constant_arithmetic", source);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(output.status.success(), "{}", stderr);
+    assert!(!stderr.contains("E0019"), "{}", stderr);
+}
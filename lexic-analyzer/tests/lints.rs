@@ -0,0 +1,205 @@
+// Niveles de los lints desde la linea de comandos y desde pragmas en el
+// codigo fuente.
+use std::env;
+use std::fs;
+use std::process::{self, Command};
+
+// `u` no se usa (linea 2) y el bloque del do esta vacio (linea 5)
+const SOURCE: &str = "program {
+    int x, u;
+    x = 1;
+    write x;
+    do {
+    } until (x > 0);
+}
+";
+
+const UNUSED: &str = "[unused_variables]: line - 2 ";
+const EMPTY: &str = "[empty_blocks]: line - 5 ";
+
+// Codigo de salida y errores del compilador con `source` y las opciones `args`
+fn compile(name: &str, source: &str, args: &[&str]) -> (i32, String) {
+    let path = env::temp_dir().join(format!("tiny-lints-{}-{}.tny", process::id(), name));
+    fs::write(&path, source).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_lexic-analyzer")).args(args).arg(&path).output().unwrap();
+    fs::remove_file(&path).unwrap();
+    (output.status.code().unwrap(), String::from_utf8(output.stderr).unwrap())
+}
+
+#[test]
+fn lints_warn_by_default() {
+    let (status, stderr) = compile("default", SOURCE, &[]);
+    assert_eq!(status, 0, "{}", stderr);
+    assert!(stderr.contains(&format!("warning{}", UNUSED)), "{}", stderr);
+    assert!(stderr.contains(&format!("warning{}", EMPTY)), "{}", stderr);
+}
+
+#[test]
+fn command_line_levels() {
+    let (status, stderr) = compile("allow", SOURCE, &["--allow", "unused_variables"]);
+    assert_eq!(status, 0, "{}", stderr);
+    assert!(!stderr.contains(UNUSED), "{}", stderr);
+    assert!(stderr.contains(&format!("warning{}", EMPTY)), "{}", stderr);
+
+    let (status, stderr) = compile("deny", SOURCE, &["--deny", "unused-variables"]);
+    assert_eq!(status, 1, "{}", stderr);
+    assert!(stderr.contains(&format!("error{}", UNUSED)), "{}", stderr);
+    assert!(stderr.contains(&format!("warning{}", EMPTY)), "{}", stderr);
+
+    // el ultimo nivel que se da a un lint es el que vale
+    let (status, stderr) = compile("last", SOURCE, &["--deny", "unused_variables", "--warn", "unused_variables"]);
+    assert_eq!(status, 0, "{}", stderr);
+    assert!(stderr.contains(&format!("warning{}", UNUSED)), "{}", stderr);
+}
+
+#[test]
+fn deny_warnings_turns_warnings_into_errors() {
+    let (status, stderr) = compile("deny_warnings", SOURCE, &["--deny-warnings", "--allow", "empty_blocks"]);
+    assert_eq!(status, 1, "{}", stderr);
+    assert!(stderr.contains(&format!("error{}", UNUSED)), "{}", stderr);
+    assert!(!stderr.contains(EMPTY), "{}", stderr);
+    assert!(!stderr.contains("warning["), "{}", stderr);
+}
+
+#[test]
+fn unknown_lint_on_the_command_line_is_rejected() {
+    let (status, stderr) = compile("unknown", SOURCE, &["--allow", "no_such_lint"]);
+    assert_eq!(status, 1);
+    assert!(stderr.starts_with("error: unknown lint 'no_such_lint'\n"), "{}", stderr);
+}
+
+#[test]
+fn pragmas_apply_to_the_whole_file() {
+    // el pragma esta despues del codigo que genera la advertencia
+    let source = format!("{}// lint: allow(unused_variables, empty_blocks)\n", SOURCE);
+    let (status, stderr) = compile("pragma_end", &source, &[]);
+    assert_eq!(status, 0, "{}", stderr);
+    assert!(stderr.is_empty(), "{}", stderr);
+
+    let source = format!("/* lint: deny(empty_blocks) */\n{}", SOURCE);
+    let (status, stderr) = compile("pragma_start", &source, &[]);
+    assert_eq!(status, 1, "{}", stderr);
+    assert!(stderr.contains("error[empty_blocks]: line - 6 "), "{}", stderr);
+}
+
+#[test]
+fn pragmas_override_the_command_line() {
+    let source = format!("{}// lint: warn(unused_variables)\n", SOURCE);
+    let (status, stderr) = compile("pragma_override", &source, &["--deny", "unused_variables", "--deny-warnings"]);
+    assert_eq!(status, 1, "{}", stderr);
+    // el pragma baja el lint a warn, pero --deny-warnings sigue valiendo
+    assert!(stderr.contains(&format!("error{}", UNUSED)), "{}", stderr);
+
+    let source = format!("{}// lint: allow(unused_variables)\n", SOURCE);
+    let (status, stderr) = compile("pragma_allow", &source, &["--deny", "unused_variables"]);
+    assert_eq!(status, 0, "{}", stderr);
+    assert!(!stderr.contains(UNUSED), "{}", stderr);
+}
+
+#[test]
+fn bad_pragmas_are_reported() {
+    let source = format!("// lint: allow(no_such_lint)\n// lint: forbid(empty_blocks)\n{}", SOURCE);
+    let (status, stderr) = compile("bad_pragma", &source, &[]);
+    assert_eq!(status, 0, "{}", stderr);
+    assert!(stderr.contains("warning[pragma]: line - 1 warning: unknown lint 'no_such_lint'"), "{}", stderr);
+    assert!(stderr.contains("warning[pragma]: line - 2 warning: malformed lint pragma 'forbid(empty_blocks)'"), "{}", stderr);
+}
+
+// Lineas con advertencias de uninitialized_variables
+fn uninitialized(name: &str, source: &str) -> Vec<u32> {
+    let (status, stderr) = compile(name, source, &["--allow", "constant_conditions"]);
+    assert_eq!(status, 0, "{}", stderr);
+    stderr
+        .lines()
+        .filter_map(|line| line.strip_prefix("warning[uninitialized_variables]: line - "))
+        .map(|line| line.split(' ').next().unwrap().parse().unwrap())
+        .collect()
+}
+
+#[test]
+fn uninitialized_variables_across_if_else() {
+    let source = "program {
+    int a, b, c, n;
+    read n;
+    if (n > 0) then {
+        a = 1;
+        b = 1;
+    } else {
+        a = 2;
+    } fi
+    if (n > 1) then {
+        c = 1;
+    } fi
+    write a;
+    write b;
+    write c;
+}
+";
+    // `a` se asigna en las dos ramas; `b` solo en una y `c` no tiene else
+    assert_eq!(uninitialized("if_else", source), [14, 15]);
+}
+
+#[test]
+fn uninitialized_variables_in_while_loops() {
+    let source = "program {
+    int i, a, b, s;
+    read i;
+    s = 0;
+    while (i > 0) {
+        a = i;
+        s = s + b;
+        b = i;
+        i = i - 1;
+    }
+    write a;
+    write s;
+}
+";
+    // el cuerpo puede no ejecutarse, y `b` se lee antes de asignarse en la
+    // primera vuelta
+    assert_eq!(uninitialized("while", source), [7, 11]);
+}
+
+#[test]
+fn uninitialized_variables_in_repeat_loops() {
+    let source = "program {
+    int i, a, b;
+    read i;
+    do {
+        a = i;
+        write b;
+        b = a;
+        i = i - 1;
+    } until (a + b < 0);
+    write a;
+    write b;
+}
+";
+    // el cuerpo se ejecuta al menos una vez, asi que `a` y `b` estan
+    // asignadas en la condicion y despues del ciclo
+    assert_eq!(uninitialized("repeat", source), [6]);
+}
+
+#[test]
+fn uninitialized_block_locals() {
+    let source = "program {
+    int i;
+    i = 0;
+    {
+        int a;
+        int b = 2;
+        write a;
+        write b;
+    }
+    while (i < 3) {
+        int c;
+        write c;
+        c = c + 1;
+        i = i + 1;
+    }
+}
+";
+    // un local sin inicializar vale 0, pero se reporta igual que cualquier
+    // otra variable
+    assert_eq!(uninitialized("block_locals", source), [7, 12, 13]);
+}
@@ -0,0 +1,35 @@
+// Compara la salida de --emit=listing con los archivos .lst de
+// tests/listing. Con UPDATE_GOLDEN=1 se reescriben.
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+mod common;
+
+// ejemplos con listado esperado
+const LISTINGS: [&str; 2] = ["test_1", "test_float"];
+
+#[test]
+fn listing_matches_golden_files() {
+    let manifest = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    for name in LISTINGS.iter() {
+        // la ruta es relativa al crate porque aparece en el listado
+        let sample = common::sample(name);
+        let output = Command::new(env!("CARGO_BIN_EXE_lexic-analyzer"))
+            .current_dir(&manifest)
+            .arg("--emit=listing")
+            .arg(sample.relative_path())
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{} did not compile", name);
+        let golden = format!("{}.lst", name);
+        let listing = String::from_utf8(output.stdout).unwrap();
+
+        let path = manifest.join("tests/listing").join(&golden);
+        if env::var_os("UPDATE_GOLDEN").is_some() {
+            fs::write(&path, &listing).unwrap();
+        }
+        assert_eq!(listing, fs::read_to_string(&path).unwrap(), "{}", golden);
+    }
+}
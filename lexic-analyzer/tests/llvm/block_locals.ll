@@ -0,0 +1,104 @@
+; ModuleID = 'block_locals.tny'
+source_filename = "block_locals.tny"
+
+@.read.int = private unnamed_addr constant [3 x i8] c"%d\00"
+@.read.float = private unnamed_addr constant [4 x i8] c"%lf\00"
+@.write.int = private unnamed_addr constant [4 x i8] c"%d\0A\00"
+@.write.float = private unnamed_addr constant [4 x i8] c"%g\0A\00"
+
+declare i32 @scanf(ptr, ...)
+declare i32 @printf(ptr, ...)
+
+; una lectura que falla da 0, como read_int/read_float del programa C
+define internal i32 @read_int() {
+entry:
+  %value = alloca i32
+  store i32 0, ptr %value
+  %count = call i32 (ptr, ...) @scanf(ptr @.read.int, ptr %value)
+  %ok = icmp eq i32 %count, 1
+  %read = load i32, ptr %value
+  %result = select i1 %ok, i32 %read, i32 0
+  ret i32 %result
+}
+
+define internal double @read_float() {
+entry:
+  %value = alloca double
+  store double 0.0, ptr %value
+  %count = call i32 (ptr, ...) @scanf(ptr @.read.float, ptr %value)
+  %ok = icmp eq i32 %count, 1
+  %read = load double, ptr %value
+  %result = select i1 %ok, double %read, double 0.0
+  ret double %result
+}
+
+define i32 @main() {
+entry:
+  %i.addr = alloca i32
+  store i32 0, ptr %i.addr
+  %x.1.addr = alloca i32
+  store i32 0, ptr %x.1.addr
+  %f.addr = alloca double
+  store double 0.0, ptr %f.addr
+  %a.addr = alloca i32
+  store i32 0, ptr %a.addr
+  %g.addr = alloca double
+  store double 0.0, ptr %g.addr
+  %b.addr = alloca i32
+  store i32 0, ptr %b.addr
+  %h.6.addr = alloca double
+  store double 0.0, ptr %h.6.addr
+  %x.7.addr = alloca i32
+  store i32 0, ptr %x.7.addr
+  %seen.addr = alloca i1
+  store i1 false, ptr %seen.addr
+  %h.9.addr = alloca double
+  store double 0.0, ptr %h.9.addr
+  store i32 1, ptr %x.1.addr
+  store i32 5, ptr %a.addr
+  store double 0x4004000000000000, ptr %g.addr
+  %t0 = load i32, ptr %a.addr
+  call i32 (ptr, ...) @printf(ptr @.write.int, i32 %t0)
+  %t1 = load double, ptr %g.addr
+  call i32 (ptr, ...) @printf(ptr @.write.float, double %t1)
+  store i32 0, ptr %i.addr
+  br label %while.cond0
+while.cond0:
+  %t2 = load i32, ptr %i.addr
+  %t3 = icmp slt i32 %t2, 4
+  br i1 %t3, label %while.body0, label %while.end0
+while.body0:
+  %t4 = load i32, ptr %b.addr
+  call i32 (ptr, ...) @printf(ptr @.write.int, i32 %t4)
+  %t5 = load i32, ptr %b.addr
+  %t6 = add i32 %t5, 1
+  store i32 %t6, ptr %b.addr
+  %t7 = load double, ptr %h.6.addr
+  %t8 = fadd double %t7, 0x3FE0000000000000
+  store double %t8, ptr %h.6.addr
+  %t9 = load i32, ptr %i.addr
+  %t10 = add i32 %t9, 1
+  store i32 %t10, ptr %i.addr
+  br label %while.cond0
+while.end0:
+  br label %repeat.body1
+repeat.body1:
+  %t11 = load i32, ptr %x.1.addr
+  %t12 = add i32 %t11, 10
+  store i32 %t12, ptr %x.7.addr
+  %t13 = load i32, ptr %x.7.addr
+  call i32 (ptr, ...) @printf(ptr @.write.int, i32 %t13)
+  %t14 = load i1, ptr %seen.addr
+  %t15 = zext i1 %t14 to i32
+  call i32 (ptr, ...) @printf(ptr @.write.int, i32 %t15)
+  store i1 true, ptr %seen.addr
+  br i1 true, label %repeat.end1, label %repeat.body1
+repeat.end1:
+  %t16 = load double, ptr %h.9.addr
+  call i32 (ptr, ...) @printf(ptr @.write.float, double %t16)
+  %t17 = load i32, ptr %x.1.addr
+  call i32 (ptr, ...) @printf(ptr @.write.int, i32 %t17)
+  %t18 = load double, ptr %f.addr
+  call i32 (ptr, ...) @printf(ptr @.write.float, double %t18)
+  ret i32 0
+}
@@ -0,0 +1,61 @@
+; ModuleID = 'test_2.tny'
+source_filename = "test_2.tny"
+
+@.read.int = private unnamed_addr constant [3 x i8] c"%d\00"
+@.read.float = private unnamed_addr constant [4 x i8] c"%lf\00"
+@.write.int = private unnamed_addr constant [4 x i8] c"%d\0A\00"
+@.write.float = private unnamed_addr constant [4 x i8] c"%g\0A\00"
+
+declare i32 @scanf(ptr, ...)
+declare i32 @printf(ptr, ...)
+
+; una lectura que falla da 0, como read_int/read_float del programa C
+define internal i32 @read_int() {
+entry:
+  %value = alloca i32
+  store i32 0, ptr %value
+  %count = call i32 (ptr, ...) @scanf(ptr @.read.int, ptr %value)
+  %ok = icmp eq i32 %count, 1
+  %read = load i32, ptr %value
+  %result = select i1 %ok, i32 %read, i32 0
+  ret i32 %result
+}
+
+define internal double @read_float() {
+entry:
+  %value = alloca double
+  store double 0.0, ptr %value
+  %count = call i32 (ptr, ...) @scanf(ptr @.read.float, ptr %value)
+  %ok = icmp eq i32 %count, 1
+  %read = load double, ptr %value
+  %result = select i1 %ok, double %read, double 0.0
+  ret double %result
+}
+
+define i32 @main() {
+entry:
+  %x.addr = alloca i32
+  store i32 0, ptr %x.addr
+  %y.addr = alloca double
+  store double 0.0, ptr %y.addr
+  store i32 0, ptr %x.addr
+  store double 0x4010000000000000, ptr %y.addr
+  br label %repeat.body0
+repeat.body0:
+  %t0 = load i32, ptr %x.addr
+  %t1 = sitofp i32 %t0 to double
+  %t2 = load double, ptr %y.addr
+  %t3 = fcmp olt double %t1, %t2
+  br i1 %t3, label %then1, label %endif1
+then1:
+  %t4 = load double, ptr %y.addr
+  %t5 = fsub double %t4, 0x3FF0000000000000
+  store double %t5, ptr %y.addr
+  br label %endif1
+endif1:
+  %t6 = load double, ptr %y.addr
+  %t7 = fcmp oge double %t6, 0x0000000000000000
+  br i1 %t7, label %repeat.end0, label %repeat.body0
+repeat.end0:
+  ret i32 0
+}
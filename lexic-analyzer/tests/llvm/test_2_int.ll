@@ -0,0 +1,60 @@
+; ModuleID = 'test_2_int.tny'
+source_filename = "test_2_int.tny"
+
+@.read.int = private unnamed_addr constant [3 x i8] c"%d\00"
+@.read.float = private unnamed_addr constant [4 x i8] c"%lf\00"
+@.write.int = private unnamed_addr constant [4 x i8] c"%d\0A\00"
+@.write.float = private unnamed_addr constant [4 x i8] c"%g\0A\00"
+
+declare i32 @scanf(ptr, ...)
+declare i32 @printf(ptr, ...)
+
+; una lectura que falla da 0, como read_int/read_float del programa C
+define internal i32 @read_int() {
+entry:
+  %value = alloca i32
+  store i32 0, ptr %value
+  %count = call i32 (ptr, ...) @scanf(ptr @.read.int, ptr %value)
+  %ok = icmp eq i32 %count, 1
+  %read = load i32, ptr %value
+  %result = select i1 %ok, i32 %read, i32 0
+  ret i32 %result
+}
+
+define internal double @read_float() {
+entry:
+  %value = alloca double
+  store double 0.0, ptr %value
+  %count = call i32 (ptr, ...) @scanf(ptr @.read.float, ptr %value)
+  %ok = icmp eq i32 %count, 1
+  %read = load double, ptr %value
+  %result = select i1 %ok, double %read, double 0.0
+  ret double %result
+}
+
+define i32 @main() {
+entry:
+  %x.addr = alloca i32
+  store i32 0, ptr %x.addr
+  %y.addr = alloca i32
+  store i32 0, ptr %y.addr
+  store i32 0, ptr %x.addr
+  store i32 4, ptr %y.addr
+  br label %repeat.body0
+repeat.body0:
+  %t0 = load i32, ptr %x.addr
+  %t1 = load i32, ptr %y.addr
+  %t2 = icmp slt i32 %t0, %t1
+  br i1 %t2, label %then1, label %endif1
+then1:
+  %t3 = load i32, ptr %y.addr
+  %t4 = sub i32 %t3, 1
+  store i32 %t4, ptr %y.addr
+  br label %endif1
+endif1:
+  %t5 = load i32, ptr %y.addr
+  %t6 = icmp sge i32 %t5, 0
+  br i1 %t6, label %repeat.end0, label %repeat.body0
+repeat.end0:
+  ret i32 0
+}
@@ -0,0 +1,102 @@
+; ModuleID = 'test_4.tny'
+source_filename = "test_4.tny"
+
+@.read.int = private unnamed_addr constant [3 x i8] c"%d\00"
+@.read.float = private unnamed_addr constant [4 x i8] c"%lf\00"
+@.write.int = private unnamed_addr constant [4 x i8] c"%d\0A\00"
+@.write.float = private unnamed_addr constant [4 x i8] c"%g\0A\00"
+
+declare i32 @scanf(ptr, ...)
+declare i32 @printf(ptr, ...)
+
+; una lectura que falla da 0, como read_int/read_float del programa C
+define internal i32 @read_int() {
+entry:
+  %value = alloca i32
+  store i32 0, ptr %value
+  %count = call i32 (ptr, ...) @scanf(ptr @.read.int, ptr %value)
+  %ok = icmp eq i32 %count, 1
+  %read = load i32, ptr %value
+  %result = select i1 %ok, i32 %read, i32 0
+  ret i32 %result
+}
+
+define internal double @read_float() {
+entry:
+  %value = alloca double
+  store double 0.0, ptr %value
+  %count = call i32 (ptr, ...) @scanf(ptr @.read.float, ptr %value)
+  %ok = icmp eq i32 %count, 1
+  %read = load double, ptr %value
+  %result = select i1 %ok, double %read, double 0.0
+  ret double %result
+}
+
+define i32 @main() {
+entry:
+  %x.addr = alloca i32
+  store i32 0, ptr %x.addr
+  %y.addr = alloca i32
+  store i32 0, ptr %y.addr
+  %a.addr = alloca double
+  store double 0.0, ptr %a.addr
+  %b.addr = alloca double
+  store double 0.0, ptr %b.addr
+  %c.addr = alloca i1
+  store i1 false, ptr %c.addr
+  %d.addr = alloca i1
+  store i1 false, ptr %d.addr
+  store i1 true, ptr %c.addr
+  store i32 5, ptr %x.addr
+  store i32 4, ptr %y.addr
+  store double 0x0000000000000000, ptr %a.addr
+  store double 0x4008000000000000, ptr %b.addr
+  br label %repeat.body0
+repeat.body0:
+  %t0 = load i32, ptr %x.addr
+  %t1 = load i32, ptr %y.addr
+  %t2 = icmp slt i32 %t0, %t1
+  br i1 %t2, label %and.rhs2, label %and.end2
+and.rhs2:
+  %t3 = load i32, ptr %y.addr
+  %t4 = icmp sge i32 %t3, 0
+  br label %and.end2
+and.end2:
+  %t5 = phi i1 [ false, %repeat.body0 ], [ %t4, %and.rhs2 ]
+  br i1 %t5, label %then1, label %else1
+then1:
+  %t6 = load i32, ptr %y.addr
+  %t7 = sub i32 %t6, 1
+  store i32 %t7, ptr %y.addr
+  br label %endif1
+else1:
+  %t8 = load i32, ptr %x.addr
+  %t9 = sub i32 %t8, 2
+  store i32 %t9, ptr %x.addr
+  %t10 = load double, ptr %a.addr
+  %t11 = load i32, ptr %x.addr
+  %t12 = sitofp i32 %t11 to double
+  %t13 = fmul double %t10, %t12
+  %t14 = load double, ptr %b.addr
+  %t15 = fadd double %t13, %t14
+  store double %t15, ptr %a.addr
+  %t16 = load i32, ptr %y.addr
+  %t17 = sub i32 %t16, 1
+  store i32 %t17, ptr %y.addr
+  br label %endif1
+endif1:
+  br label %while.cond3
+while.cond3:
+  %t18 = load double, ptr %a.addr
+  %t19 = fcmp oeq double %t18, 0x4008000000000000
+  br i1 %t19, label %while.body3, label %while.end3
+while.body3:
+  %t20 = load double, ptr %a.addr
+  call i32 (ptr, ...) @printf(ptr @.write.float, double %t20)
+  br label %while.cond3
+while.end3:
+  %t21 = load i1, ptr %c.addr
+  br i1 %t21, label %repeat.end0, label %repeat.body0
+repeat.end0:
+  ret i32 0
+}
@@ -0,0 +1,142 @@
+; ModuleID = 'test_expr.tny'
+source_filename = "test_expr.tny"
+
+@.read.int = private unnamed_addr constant [3 x i8] c"%d\00"
+@.read.float = private unnamed_addr constant [4 x i8] c"%lf\00"
+@.write.int = private unnamed_addr constant [4 x i8] c"%d\0A\00"
+@.write.float = private unnamed_addr constant [4 x i8] c"%g\0A\00"
+
+declare i32 @scanf(ptr, ...)
+declare i32 @printf(ptr, ...)
+
+; una lectura que falla da 0, como read_int/read_float del programa C
+define internal i32 @read_int() {
+entry:
+  %value = alloca i32
+  store i32 0, ptr %value
+  %count = call i32 (ptr, ...) @scanf(ptr @.read.int, ptr %value)
+  %ok = icmp eq i32 %count, 1
+  %read = load i32, ptr %value
+  %result = select i1 %ok, i32 %read, i32 0
+  ret i32 %result
+}
+
+define internal double @read_float() {
+entry:
+  %value = alloca double
+  store double 0.0, ptr %value
+  %count = call i32 (ptr, ...) @scanf(ptr @.read.float, ptr %value)
+  %ok = icmp eq i32 %count, 1
+  %read = load double, ptr %value
+  %result = select i1 %ok, double %read, double 0.0
+  ret double %result
+}
+
+define i32 @main() {
+entry:
+  %a.addr = alloca i32
+  store i32 0, ptr %a.addr
+  %b.addr = alloca i32
+  store i32 0, ptr %b.addr
+  %c.addr = alloca i32
+  store i32 0, ptr %c.addr
+  %d.addr = alloca i32
+  store i32 0, ptr %d.addr
+  %f.addr = alloca double
+  store double 0.0, ptr %f.addr
+  %ok.addr = alloca i1
+  store i1 false, ptr %ok.addr
+  %t0 = call i32 @read_int()
+  store i32 %t0, ptr %a.addr
+  %t1 = call i32 @read_int()
+  store i32 %t1, ptr %b.addr
+  %t2 = load i32, ptr %a.addr
+  %t3 = load i32, ptr %b.addr
+  %t4 = add i32 %t2, %t3
+  %t5 = load i32, ptr %a.addr
+  %t6 = load i32, ptr %b.addr
+  %t7 = sub i32 %t5, %t6
+  %t8 = mul i32 %t4, %t7
+  %t9 = load i32, ptr %a.addr
+  %t10 = mul i32 %t9, 2
+  %t11 = load i32, ptr %b.addr
+  %t12 = sub i32 %t10, %t11
+  %t13 = load i32, ptr %b.addr
+  %t14 = load i32, ptr %a.addr
+  %t15 = sub i32 %t14, 1
+  %t16 = mul i32 3, %t15
+  %t17 = add i32 %t13, %t16
+  %t18 = mul i32 %t12, %t17
+  %t19 = add i32 %t8, %t18
+  store i32 %t19, ptr %c.addr
+  %t20 = load i32, ptr %c.addr
+  call i32 (ptr, ...) @printf(ptr @.write.int, i32 %t20)
+  %t21 = load i32, ptr %a.addr
+  %t22 = add i32 %t21, 1
+  %t23 = load i32, ptr %b.addr
+  %t24 = add i32 %t23, 2
+  %t25 = mul i32 %t22, %t24
+  %t26 = load i32, ptr %a.addr
+  %t27 = add i32 %t26, 3
+  %t28 = load i32, ptr %b.addr
+  %t29 = add i32 %t28, 4
+  %t30 = mul i32 %t27, %t29
+  %t31 = sub i32 %t25, %t30
+  %t32 = load i32, ptr %a.addr
+  %t33 = add i32 %t32, 5
+  %t34 = load i32, ptr %b.addr
+  %t35 = sub i32 %t34, 6
+  %t36 = load i32, ptr %a.addr
+  %t37 = add i32 %t36, 7
+  %t38 = mul i32 %t35, %t37
+  %t39 = sub i32 %t33, %t38
+  %t40 = load i32, ptr %a.addr
+  %t41 = add i32 %t40, 8
+  %t42 = load i32, ptr %b.addr
+  %t43 = add i32 %t42, 9
+  %t44 = mul i32 %t41, %t43
+  %t45 = add i32 %t39, %t44
+  %t46 = sdiv i32 %t31, %t45
+  store i32 %t46, ptr %d.addr
+  %t47 = load i32, ptr %d.addr
+  call i32 (ptr, ...) @printf(ptr @.write.int, i32 %t47)
+  %t48 = load i32, ptr %a.addr
+  %t49 = sitofp i32 %t48 to double
+  %t50 = fadd double %t49, 0x3FE0000000000000
+  %t51 = load i32, ptr %b.addr
+  %t52 = sitofp i32 %t51 to double
+  %t53 = fsub double %t52, 0x3FD0000000000000
+  %t54 = fmul double %t50, %t53
+  %t55 = load i32, ptr %a.addr
+  %t56 = load i32, ptr %b.addr
+  %t57 = mul i32 %t55, %t56
+  %t58 = sitofp i32 %t57 to double
+  %t59 = fadd double %t58, 0x3FF8000000000000
+  %t60 = fdiv double %t54, %t59
+  store double %t60, ptr %f.addr
+  %t61 = load double, ptr %f.addr
+  call i32 (ptr, ...) @printf(ptr @.write.float, double %t61)
+  %t62 = load i32, ptr %a.addr
+  %t63 = load i32, ptr %b.addr
+  %t64 = add i32 %t62, %t63
+  %t65 = mul i32 %t64, 2
+  %t66 = load i32, ptr %a.addr
+  %t67 = load i32, ptr %b.addr
+  %t68 = sub i32 %t66, %t67
+  %t69 = mul i32 %t68, 3
+  %t70 = icmp sgt i32 %t65, %t69
+  br i1 %t70, label %and.rhs0, label %and.end0
+and.rhs0:
+  %t71 = load i32, ptr %c.addr
+  %t72 = load i32, ptr %d.addr
+  %t73 = icmp eq i32 %t71, %t72
+  %t74 = xor i1 %t73, true
+  br label %and.end0
+and.end0:
+  %t75 = phi i1 [ false, %entry ], [ %t74, %and.rhs0 ]
+  store i1 %t75, ptr %ok.addr
+  %t76 = load i1, ptr %ok.addr
+  %t77 = zext i1 %t76 to i32
+  call i32 (ptr, ...) @printf(ptr @.write.int, i32 %t77)
+  ret i32 0
+}
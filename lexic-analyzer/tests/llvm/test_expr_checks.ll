@@ -0,0 +1,392 @@
+; ModuleID = 'test_expr.tny'
+source_filename = "test_expr.tny"
+
+@.read.int = private unnamed_addr constant [3 x i8] c"%d\00"
+@.read.float = private unnamed_addr constant [4 x i8] c"%lf\00"
+@.write.int = private unnamed_addr constant [4 x i8] c"%d\0A\00"
+@.write.float = private unnamed_addr constant [4 x i8] c"%g\0A\00"
+
+declare i32 @scanf(ptr, ...)
+declare i32 @printf(ptr, ...)
+
+; una lectura que falla da 0, como read_int/read_float del programa C
+define internal i32 @read_int() {
+entry:
+  %value = alloca i32
+  store i32 0, ptr %value
+  %count = call i32 (ptr, ...) @scanf(ptr @.read.int, ptr %value)
+  %ok = icmp eq i32 %count, 1
+  %read = load i32, ptr %value
+  %result = select i1 %ok, i32 %read, i32 0
+  ret i32 %result
+}
+
+define internal double @read_float() {
+entry:
+  %value = alloca double
+  store double 0.0, ptr %value
+  %count = call i32 (ptr, ...) @scanf(ptr @.read.float, ptr %value)
+  %ok = icmp eq i32 %count, 1
+  %read = load double, ptr %value
+  %result = select i1 %ok, double %read, double 0.0
+  ret double %result
+}
+
+declare void @exit(i32)
+declare { i32, i1 } @llvm.sadd.with.overflow.i32(i32, i32)
+declare { i32, i1 } @llvm.ssub.with.overflow.i32(i32, i32)
+declare { i32, i1 } @llvm.smul.with.overflow.i32(i32, i32)
+
+define internal void @trap(i32 %code, i32 %line) noreturn {
+entry:
+  call i32 (ptr, ...) @printf(ptr @.write.int, i32 %code)
+  call i32 (ptr, ...) @printf(ptr @.write.int, i32 %line)
+  call void @exit(i32 1)
+  unreachable
+}
+
+define i32 @main() {
+entry:
+  %a.addr = alloca i32
+  store i32 0, ptr %a.addr
+  %b.addr = alloca i32
+  store i32 0, ptr %b.addr
+  %c.addr = alloca i32
+  store i32 0, ptr %c.addr
+  %d.addr = alloca i32
+  store i32 0, ptr %d.addr
+  %f.addr = alloca double
+  store double 0.0, ptr %f.addr
+  %ok.addr = alloca i1
+  store i1 false, ptr %ok.addr
+  %t0 = call i32 @read_int()
+  store i32 %t0, ptr %a.addr
+  %t1 = call i32 @read_int()
+  store i32 %t1, ptr %b.addr
+  %t2 = load i32, ptr %a.addr
+  %t3 = load i32, ptr %b.addr
+  %t4 = call { i32, i1 } @llvm.sadd.with.overflow.i32(i32 %t2, i32 %t3)
+  %t5 = extractvalue { i32, i1 } %t4, 1
+  br i1 %t5, label %trap0, label %ok0
+trap0:
+  call void @trap(i32 2, i32 6)
+  unreachable
+ok0:
+  %t6 = extractvalue { i32, i1 } %t4, 0
+  %t7 = load i32, ptr %a.addr
+  %t8 = load i32, ptr %b.addr
+  %t9 = call { i32, i1 } @llvm.ssub.with.overflow.i32(i32 %t7, i32 %t8)
+  %t10 = extractvalue { i32, i1 } %t9, 1
+  br i1 %t10, label %trap1, label %ok1
+trap1:
+  call void @trap(i32 2, i32 6)
+  unreachable
+ok1:
+  %t11 = extractvalue { i32, i1 } %t9, 0
+  %t12 = call { i32, i1 } @llvm.smul.with.overflow.i32(i32 %t6, i32 %t11)
+  %t13 = extractvalue { i32, i1 } %t12, 1
+  br i1 %t13, label %trap2, label %ok2
+trap2:
+  call void @trap(i32 2, i32 6)
+  unreachable
+ok2:
+  %t14 = extractvalue { i32, i1 } %t12, 0
+  %t15 = load i32, ptr %a.addr
+  %t16 = call { i32, i1 } @llvm.smul.with.overflow.i32(i32 %t15, i32 2)
+  %t17 = extractvalue { i32, i1 } %t16, 1
+  br i1 %t17, label %trap3, label %ok3
+trap3:
+  call void @trap(i32 2, i32 6)
+  unreachable
+ok3:
+  %t18 = extractvalue { i32, i1 } %t16, 0
+  %t19 = load i32, ptr %b.addr
+  %t20 = call { i32, i1 } @llvm.ssub.with.overflow.i32(i32 %t18, i32 %t19)
+  %t21 = extractvalue { i32, i1 } %t20, 1
+  br i1 %t21, label %trap4, label %ok4
+trap4:
+  call void @trap(i32 2, i32 6)
+  unreachable
+ok4:
+  %t22 = extractvalue { i32, i1 } %t20, 0
+  %t23 = load i32, ptr %b.addr
+  %t24 = load i32, ptr %a.addr
+  %t25 = call { i32, i1 } @llvm.ssub.with.overflow.i32(i32 %t24, i32 1)
+  %t26 = extractvalue { i32, i1 } %t25, 1
+  br i1 %t26, label %trap5, label %ok5
+trap5:
+  call void @trap(i32 2, i32 6)
+  unreachable
+ok5:
+  %t27 = extractvalue { i32, i1 } %t25, 0
+  %t28 = call { i32, i1 } @llvm.smul.with.overflow.i32(i32 3, i32 %t27)
+  %t29 = extractvalue { i32, i1 } %t28, 1
+  br i1 %t29, label %trap6, label %ok6
+trap6:
+  call void @trap(i32 2, i32 6)
+  unreachable
+ok6:
+  %t30 = extractvalue { i32, i1 } %t28, 0
+  %t31 = call { i32, i1 } @llvm.sadd.with.overflow.i32(i32 %t23, i32 %t30)
+  %t32 = extractvalue { i32, i1 } %t31, 1
+  br i1 %t32, label %trap7, label %ok7
+trap7:
+  call void @trap(i32 2, i32 6)
+  unreachable
+ok7:
+  %t33 = extractvalue { i32, i1 } %t31, 0
+  %t34 = call { i32, i1 } @llvm.smul.with.overflow.i32(i32 %t22, i32 %t33)
+  %t35 = extractvalue { i32, i1 } %t34, 1
+  br i1 %t35, label %trap8, label %ok8
+trap8:
+  call void @trap(i32 2, i32 6)
+  unreachable
+ok8:
+  %t36 = extractvalue { i32, i1 } %t34, 0
+  %t37 = call { i32, i1 } @llvm.sadd.with.overflow.i32(i32 %t14, i32 %t36)
+  %t38 = extractvalue { i32, i1 } %t37, 1
+  br i1 %t38, label %trap9, label %ok9
+trap9:
+  call void @trap(i32 2, i32 6)
+  unreachable
+ok9:
+  %t39 = extractvalue { i32, i1 } %t37, 0
+  store i32 %t39, ptr %c.addr
+  %t40 = load i32, ptr %c.addr
+  call i32 (ptr, ...) @printf(ptr @.write.int, i32 %t40)
+  %t41 = load i32, ptr %a.addr
+  %t42 = call { i32, i1 } @llvm.sadd.with.overflow.i32(i32 %t41, i32 1)
+  %t43 = extractvalue { i32, i1 } %t42, 1
+  br i1 %t43, label %trap10, label %ok10
+trap10:
+  call void @trap(i32 2, i32 8)
+  unreachable
+ok10:
+  %t44 = extractvalue { i32, i1 } %t42, 0
+  %t45 = load i32, ptr %b.addr
+  %t46 = call { i32, i1 } @llvm.sadd.with.overflow.i32(i32 %t45, i32 2)
+  %t47 = extractvalue { i32, i1 } %t46, 1
+  br i1 %t47, label %trap11, label %ok11
+trap11:
+  call void @trap(i32 2, i32 8)
+  unreachable
+ok11:
+  %t48 = extractvalue { i32, i1 } %t46, 0
+  %t49 = call { i32, i1 } @llvm.smul.with.overflow.i32(i32 %t44, i32 %t48)
+  %t50 = extractvalue { i32, i1 } %t49, 1
+  br i1 %t50, label %trap12, label %ok12
+trap12:
+  call void @trap(i32 2, i32 8)
+  unreachable
+ok12:
+  %t51 = extractvalue { i32, i1 } %t49, 0
+  %t52 = load i32, ptr %a.addr
+  %t53 = call { i32, i1 } @llvm.sadd.with.overflow.i32(i32 %t52, i32 3)
+  %t54 = extractvalue { i32, i1 } %t53, 1
+  br i1 %t54, label %trap13, label %ok13
+trap13:
+  call void @trap(i32 2, i32 8)
+  unreachable
+ok13:
+  %t55 = extractvalue { i32, i1 } %t53, 0
+  %t56 = load i32, ptr %b.addr
+  %t57 = call { i32, i1 } @llvm.sadd.with.overflow.i32(i32 %t56, i32 4)
+  %t58 = extractvalue { i32, i1 } %t57, 1
+  br i1 %t58, label %trap14, label %ok14
+trap14:
+  call void @trap(i32 2, i32 8)
+  unreachable
+ok14:
+  %t59 = extractvalue { i32, i1 } %t57, 0
+  %t60 = call { i32, i1 } @llvm.smul.with.overflow.i32(i32 %t55, i32 %t59)
+  %t61 = extractvalue { i32, i1 } %t60, 1
+  br i1 %t61, label %trap15, label %ok15
+trap15:
+  call void @trap(i32 2, i32 8)
+  unreachable
+ok15:
+  %t62 = extractvalue { i32, i1 } %t60, 0
+  %t63 = call { i32, i1 } @llvm.ssub.with.overflow.i32(i32 %t51, i32 %t62)
+  %t64 = extractvalue { i32, i1 } %t63, 1
+  br i1 %t64, label %trap16, label %ok16
+trap16:
+  call void @trap(i32 2, i32 8)
+  unreachable
+ok16:
+  %t65 = extractvalue { i32, i1 } %t63, 0
+  %t66 = load i32, ptr %a.addr
+  %t67 = call { i32, i1 } @llvm.sadd.with.overflow.i32(i32 %t66, i32 5)
+  %t68 = extractvalue { i32, i1 } %t67, 1
+  br i1 %t68, label %trap17, label %ok17
+trap17:
+  call void @trap(i32 2, i32 8)
+  unreachable
+ok17:
+  %t69 = extractvalue { i32, i1 } %t67, 0
+  %t70 = load i32, ptr %b.addr
+  %t71 = call { i32, i1 } @llvm.ssub.with.overflow.i32(i32 %t70, i32 6)
+  %t72 = extractvalue { i32, i1 } %t71, 1
+  br i1 %t72, label %trap18, label %ok18
+trap18:
+  call void @trap(i32 2, i32 8)
+  unreachable
+ok18:
+  %t73 = extractvalue { i32, i1 } %t71, 0
+  %t74 = load i32, ptr %a.addr
+  %t75 = call { i32, i1 } @llvm.sadd.with.overflow.i32(i32 %t74, i32 7)
+  %t76 = extractvalue { i32, i1 } %t75, 1
+  br i1 %t76, label %trap19, label %ok19
+trap19:
+  call void @trap(i32 2, i32 8)
+  unreachable
+ok19:
+  %t77 = extractvalue { i32, i1 } %t75, 0
+  %t78 = call { i32, i1 } @llvm.smul.with.overflow.i32(i32 %t73, i32 %t77)
+  %t79 = extractvalue { i32, i1 } %t78, 1
+  br i1 %t79, label %trap20, label %ok20
+trap20:
+  call void @trap(i32 2, i32 8)
+  unreachable
+ok20:
+  %t80 = extractvalue { i32, i1 } %t78, 0
+  %t81 = call { i32, i1 } @llvm.ssub.with.overflow.i32(i32 %t69, i32 %t80)
+  %t82 = extractvalue { i32, i1 } %t81, 1
+  br i1 %t82, label %trap21, label %ok21
+trap21:
+  call void @trap(i32 2, i32 8)
+  unreachable
+ok21:
+  %t83 = extractvalue { i32, i1 } %t81, 0
+  %t84 = load i32, ptr %a.addr
+  %t85 = call { i32, i1 } @llvm.sadd.with.overflow.i32(i32 %t84, i32 8)
+  %t86 = extractvalue { i32, i1 } %t85, 1
+  br i1 %t86, label %trap22, label %ok22
+trap22:
+  call void @trap(i32 2, i32 8)
+  unreachable
+ok22:
+  %t87 = extractvalue { i32, i1 } %t85, 0
+  %t88 = load i32, ptr %b.addr
+  %t89 = call { i32, i1 } @llvm.sadd.with.overflow.i32(i32 %t88, i32 9)
+  %t90 = extractvalue { i32, i1 } %t89, 1
+  br i1 %t90, label %trap23, label %ok23
+trap23:
+  call void @trap(i32 2, i32 8)
+  unreachable
+ok23:
+  %t91 = extractvalue { i32, i1 } %t89, 0
+  %t92 = call { i32, i1 } @llvm.smul.with.overflow.i32(i32 %t87, i32 %t91)
+  %t93 = extractvalue { i32, i1 } %t92, 1
+  br i1 %t93, label %trap24, label %ok24
+trap24:
+  call void @trap(i32 2, i32 8)
+  unreachable
+ok24:
+  %t94 = extractvalue { i32, i1 } %t92, 0
+  %t95 = call { i32, i1 } @llvm.sadd.with.overflow.i32(i32 %t83, i32 %t94)
+  %t96 = extractvalue { i32, i1 } %t95, 1
+  br i1 %t96, label %trap25, label %ok25
+trap25:
+  call void @trap(i32 2, i32 8)
+  unreachable
+ok25:
+  %t97 = extractvalue { i32, i1 } %t95, 0
+  %t98 = icmp eq i32 %t97, 0
+  br i1 %t98, label %trap.div26, label %check.div26
+trap.div26:
+  call void @trap(i32 1, i32 8)
+  unreachable
+check.div26:
+  %t99 = icmp eq i32 %t65, -2147483648
+  %t100 = icmp eq i32 %t97, -1
+  %t101 = and i1 %t99, %t100
+  br i1 %t101, label %trap26, label %ok26
+trap26:
+  call void @trap(i32 2, i32 8)
+  unreachable
+ok26:
+  %t102 = sdiv i32 %t65, %t97
+  store i32 %t102, ptr %d.addr
+  %t103 = load i32, ptr %d.addr
+  call i32 (ptr, ...) @printf(ptr @.write.int, i32 %t103)
+  %t104 = load i32, ptr %a.addr
+  %t105 = sitofp i32 %t104 to double
+  %t106 = fadd double %t105, 0x3FE0000000000000
+  %t107 = load i32, ptr %b.addr
+  %t108 = sitofp i32 %t107 to double
+  %t109 = fsub double %t108, 0x3FD0000000000000
+  %t110 = fmul double %t106, %t109
+  %t111 = load i32, ptr %a.addr
+  %t112 = load i32, ptr %b.addr
+  %t113 = call { i32, i1 } @llvm.smul.with.overflow.i32(i32 %t111, i32 %t112)
+  %t114 = extractvalue { i32, i1 } %t113, 1
+  br i1 %t114, label %trap27, label %ok27
+trap27:
+  call void @trap(i32 2, i32 10)
+  unreachable
+ok27:
+  %t115 = extractvalue { i32, i1 } %t113, 0
+  %t116 = sitofp i32 %t115 to double
+  %t117 = fadd double %t116, 0x3FF8000000000000
+  %t118 = fcmp oeq double %t117, 0.0
+  br i1 %t118, label %trap.div28, label %check.div28
+trap.div28:
+  call void @trap(i32 1, i32 10)
+  unreachable
+check.div28:
+  %t119 = fdiv double %t110, %t117
+  store double %t119, ptr %f.addr
+  %t120 = load double, ptr %f.addr
+  call i32 (ptr, ...) @printf(ptr @.write.float, double %t120)
+  %t121 = load i32, ptr %a.addr
+  %t122 = load i32, ptr %b.addr
+  %t123 = call { i32, i1 } @llvm.sadd.with.overflow.i32(i32 %t121, i32 %t122)
+  %t124 = extractvalue { i32, i1 } %t123, 1
+  br i1 %t124, label %trap30, label %ok30
+trap30:
+  call void @trap(i32 2, i32 12)
+  unreachable
+ok30:
+  %t125 = extractvalue { i32, i1 } %t123, 0
+  %t126 = call { i32, i1 } @llvm.smul.with.overflow.i32(i32 %t125, i32 2)
+  %t127 = extractvalue { i32, i1 } %t126, 1
+  br i1 %t127, label %trap31, label %ok31
+trap31:
+  call void @trap(i32 2, i32 12)
+  unreachable
+ok31:
+  %t128 = extractvalue { i32, i1 } %t126, 0
+  %t129 = load i32, ptr %a.addr
+  %t130 = load i32, ptr %b.addr
+  %t131 = call { i32, i1 } @llvm.ssub.with.overflow.i32(i32 %t129, i32 %t130)
+  %t132 = extractvalue { i32, i1 } %t131, 1
+  br i1 %t132, label %trap32, label %ok32
+trap32:
+  call void @trap(i32 2, i32 12)
+  unreachable
+ok32:
+  %t133 = extractvalue { i32, i1 } %t131, 0
+  %t134 = call { i32, i1 } @llvm.smul.with.overflow.i32(i32 %t133, i32 3)
+  %t135 = extractvalue { i32, i1 } %t134, 1
+  br i1 %t135, label %trap33, label %ok33
+trap33:
+  call void @trap(i32 2, i32 12)
+  unreachable
+ok33:
+  %t136 = extractvalue { i32, i1 } %t134, 0
+  %t137 = icmp sgt i32 %t128, %t136
+  br i1 %t137, label %and.rhs29, label %and.end29
+and.rhs29:
+  %t138 = load i32, ptr %c.addr
+  %t139 = load i32, ptr %d.addr
+  %t140 = icmp eq i32 %t138, %t139
+  %t141 = xor i1 %t140, true
+  br label %and.end29
+and.end29:
+  %t142 = phi i1 [ false, %ok33 ], [ %t141, %and.rhs29 ]
+  store i1 %t142, ptr %ok.addr
+  %t143 = load i1, ptr %ok.addr
+  %t144 = zext i1 %t143 to i32
+  call i32 (ptr, ...) @printf(ptr @.write.int, i32 %t144)
+  ret i32 0
+}
@@ -0,0 +1,92 @@
+; ModuleID = 'test_float.tny'
+source_filename = "test_float.tny"
+
+@.read.int = private unnamed_addr constant [3 x i8] c"%d\00"
+@.read.float = private unnamed_addr constant [4 x i8] c"%lf\00"
+@.write.int = private unnamed_addr constant [4 x i8] c"%d\0A\00"
+@.write.float = private unnamed_addr constant [4 x i8] c"%g\0A\00"
+
+declare i32 @scanf(ptr, ...)
+declare i32 @printf(ptr, ...)
+
+; una lectura que falla da 0, como read_int/read_float del programa C
+define internal i32 @read_int() {
+entry:
+  %value = alloca i32
+  store i32 0, ptr %value
+  %count = call i32 (ptr, ...) @scanf(ptr @.read.int, ptr %value)
+  %ok = icmp eq i32 %count, 1
+  %read = load i32, ptr %value
+  %result = select i1 %ok, i32 %read, i32 0
+  ret i32 %result
+}
+
+define internal double @read_float() {
+entry:
+  %value = alloca double
+  store double 0.0, ptr %value
+  %count = call i32 (ptr, ...) @scanf(ptr @.read.float, ptr %value)
+  %ok = icmp eq i32 %count, 1
+  %read = load double, ptr %value
+  %result = select i1 %ok, double %read, double 0.0
+  ret double %result
+}
+
+define i32 @main() {
+entry:
+  %n.addr = alloca i32
+  store i32 0, ptr %n.addr
+  %i.addr = alloca i32
+  store i32 0, ptr %i.addr
+  %x.addr = alloca double
+  store double 0.0, ptr %x.addr
+  %suma.addr = alloca double
+  store double 0.0, ptr %suma.addr
+  %promedio.addr = alloca double
+  store double 0.0, ptr %promedio.addr
+  %t0 = call i32 @read_int()
+  store i32 %t0, ptr %n.addr
+  store double 0x0000000000000000, ptr %suma.addr
+  store i32 0, ptr %i.addr
+  br label %while.cond0
+while.cond0:
+  %t1 = load i32, ptr %i.addr
+  %t2 = load i32, ptr %n.addr
+  %t3 = icmp slt i32 %t1, %t2
+  br i1 %t3, label %while.body0, label %while.end0
+while.body0:
+  %t4 = call double @read_float()
+  store double %t4, ptr %x.addr
+  %t5 = load double, ptr %suma.addr
+  %t6 = load double, ptr %x.addr
+  %t7 = fadd double %t5, %t6
+  store double %t7, ptr %suma.addr
+  %t8 = load i32, ptr %i.addr
+  %t9 = add i32 %t8, 1
+  store i32 %t9, ptr %i.addr
+  br label %while.cond0
+while.end0:
+  %t10 = load double, ptr %suma.addr
+  %t11 = load i32, ptr %n.addr
+  %t12 = sitofp i32 %t11 to double
+  %t13 = fdiv double %t10, %t12
+  store double %t13, ptr %promedio.addr
+  %t14 = load double, ptr %promedio.addr
+  call i32 (ptr, ...) @printf(ptr @.write.float, double %t14)
+  %t15 = load double, ptr %promedio.addr
+  %t16 = fmul double %t15, 0x4059000000000000
+  %t17 = fptosi double %t16 to i32
+  %t18 = sdiv i32 %t17, 100
+  call i32 (ptr, ...) @printf(ptr @.write.int, i32 %t18)
+  %t19 = load double, ptr %promedio.addr
+  %t20 = fcmp oge double %t19, 0x4004000000000000
+  br i1 %t20, label %then1, label %else1
+then1:
+  call i32 (ptr, ...) @printf(ptr @.write.float, double 0x4014000000000000)
+  br label %endif1
+else1:
+  call i32 (ptr, ...) @printf(ptr @.write.float, double 0x3FE0000000000000)
+  br label %endif1
+endif1:
+  ret i32 0
+}
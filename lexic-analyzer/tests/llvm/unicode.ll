@@ -0,0 +1,64 @@
+; ModuleID = 'unicode.tny'
+source_filename = "unicode.tny"
+
+@.read.int = private unnamed_addr constant [3 x i8] c"%d\00"
+@.read.float = private unnamed_addr constant [4 x i8] c"%lf\00"
+@.write.int = private unnamed_addr constant [4 x i8] c"%d\0A\00"
+@.write.float = private unnamed_addr constant [4 x i8] c"%g\0A\00"
+
+declare i32 @scanf(ptr, ...)
+declare i32 @printf(ptr, ...)
+
+; una lectura que falla da 0, como read_int/read_float del programa C
+define internal i32 @read_int() {
+entry:
+  %value = alloca i32
+  store i32 0, ptr %value
+  %count = call i32 (ptr, ...) @scanf(ptr @.read.int, ptr %value)
+  %ok = icmp eq i32 %count, 1
+  %read = load i32, ptr %value
+  %result = select i1 %ok, i32 %read, i32 0
+  ret i32 %result
+}
+
+define internal double @read_float() {
+entry:
+  %value = alloca double
+  store double 0.0, ptr %value
+  %count = call i32 (ptr, ...) @scanf(ptr @.read.float, ptr %value)
+  %ok = icmp eq i32 %count, 1
+  %read = load double, ptr %value
+  %result = select i1 %ok, double %read, double 0.0
+  ret double %result
+}
+
+define i32 @main() {
+entry:
+  %a.uf1.o.0.addr = alloca i32
+  store i32 0, ptr %a.uf1.o.0.addr
+  %n.addr = alloca i32
+  store i32 0, ptr %n.addr
+  %.u3c0..addr = alloca double
+  store double 0.0, ptr %.u3c0..addr
+  %a.uf1.o.3.addr = alloca i32
+  store i32 0, ptr %a.uf1.o.3.addr
+  store i32 2024, ptr %a.uf1.o.0.addr
+  store double 0x400921F9F01B866E, ptr %.u3c0..addr
+  %t0 = call i32 @read_int()
+  store i32 %t0, ptr %n.addr
+  br label %repeat.body0
+repeat.body0:
+  %t1 = load i32, ptr %n.addr
+  %t2 = mul i32 %t1, 2
+  store i32 %t2, ptr %a.uf1.o.3.addr
+  %t3 = load i32, ptr %a.uf1.o.3.addr
+  call i32 (ptr, ...) @printf(ptr @.write.int, i32 %t3)
+  br i1 true, label %repeat.end0, label %repeat.body0
+repeat.end0:
+  %t4 = load i32, ptr %a.uf1.o.0.addr
+  call i32 (ptr, ...) @printf(ptr @.write.int, i32 %t4)
+  %t5 = load double, ptr %.u3c0..addr
+  %t6 = fmul double %t5, 0x4000000000000000
+  call i32 (ptr, ...) @printf(ptr @.write.float, double %t6)
+  ret i32 0
+}
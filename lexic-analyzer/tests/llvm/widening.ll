@@ -0,0 +1,127 @@
+; ModuleID = 'widening.tny'
+source_filename = "widening.tny"
+
+@.read.int = private unnamed_addr constant [3 x i8] c"%d\00"
+@.read.float = private unnamed_addr constant [4 x i8] c"%lf\00"
+@.write.int = private unnamed_addr constant [4 x i8] c"%d\0A\00"
+@.write.float = private unnamed_addr constant [4 x i8] c"%g\0A\00"
+
+declare i32 @scanf(ptr, ...)
+declare i32 @printf(ptr, ...)
+
+; una lectura que falla da 0, como read_int/read_float del programa C
+define internal i32 @read_int() {
+entry:
+  %value = alloca i32
+  store i32 0, ptr %value
+  %count = call i32 (ptr, ...) @scanf(ptr @.read.int, ptr %value)
+  %ok = icmp eq i32 %count, 1
+  %read = load i32, ptr %value
+  %result = select i1 %ok, i32 %read, i32 0
+  ret i32 %result
+}
+
+define internal double @read_float() {
+entry:
+  %value = alloca double
+  store double 0.0, ptr %value
+  %count = call i32 (ptr, ...) @scanf(ptr @.read.float, ptr %value)
+  %ok = icmp eq i32 %count, 1
+  %read = load double, ptr %value
+  %result = select i1 %ok, double %read, double 0.0
+  ret double %result
+}
+
+define i32 @main() {
+entry:
+  %i.addr = alloca i32
+  store i32 0, ptr %i.addr
+  %n.addr = alloca i32
+  store i32 0, ptr %n.addr
+  %f.addr = alloca double
+  store double 0.0, ptr %f.addr
+  %g.addr = alloca double
+  store double 0.0, ptr %g.addr
+  %mayor.addr = alloca i1
+  store i1 false, ptr %mayor.addr
+  %h.addr = alloca double
+  store double 0.0, ptr %h.addr
+  %t0 = call i32 @read_int()
+  store i32 %t0, ptr %i.addr
+  store double 0x3FE0000000000000, ptr %f.addr
+  %t1 = load i32, ptr %i.addr
+  %t2 = sitofp i32 %t1 to double
+  store double %t2, ptr %g.addr
+  %t3 = load double, ptr %g.addr
+  call i32 (ptr, ...) @printf(ptr @.write.float, double %t3)
+  %t4 = load i32, ptr %i.addr
+  %t5 = sitofp i32 %t4 to double
+  %t6 = load double, ptr %f.addr
+  %t7 = fadd double %t5, %t6
+  call i32 (ptr, ...) @printf(ptr @.write.float, double %t7)
+  %t8 = load double, ptr %f.addr
+  %t9 = load i32, ptr %i.addr
+  %t10 = sitofp i32 %t9 to double
+  %t11 = fmul double %t8, %t10
+  call i32 (ptr, ...) @printf(ptr @.write.float, double %t11)
+  %t12 = load i32, ptr %i.addr
+  %t13 = sdiv i32 %t12, 2
+  call i32 (ptr, ...) @printf(ptr @.write.int, i32 %t13)
+  %t14 = load i32, ptr %i.addr
+  %t15 = sitofp i32 %t14 to double
+  %t16 = fdiv double %t15, 0x4000000000000000
+  call i32 (ptr, ...) @printf(ptr @.write.float, double %t16)
+  %t17 = load i32, ptr %i.addr
+  %t18 = add i32 %t17, 1
+  %t19 = sitofp i32 %t18 to double
+  %t20 = load double, ptr %f.addr
+  %t21 = fmul double %t19, %t20
+  call i32 (ptr, ...) @printf(ptr @.write.float, double %t21)
+  %t22 = load i32, ptr %i.addr
+  %t23 = sitofp i32 %t22 to double
+  %t24 = fdiv double %t23, 0x4000000000000000
+  call i32 (ptr, ...) @printf(ptr @.write.float, double %t24)
+  %t25 = load i32, ptr %i.addr
+  %t26 = sitofp i32 %t25 to double
+  %t27 = load double, ptr %f.addr
+  %t28 = fmul double %t26, %t27
+  %t29 = fptosi double %t28 to i32
+  store i32 %t29, ptr %n.addr
+  %t30 = load i32, ptr %n.addr
+  call i32 (ptr, ...) @printf(ptr @.write.int, i32 %t30)
+  %t31 = load i32, ptr %i.addr
+  %t32 = sub i32 %t31, 7
+  %t33 = sitofp i32 %t32 to double
+  %t34 = fmul double %t33, 0x3FD0000000000000
+  store double %t34, ptr %g.addr
+  %t35 = load double, ptr %g.addr
+  call i32 (ptr, ...) @printf(ptr @.write.float, double %t35)
+  %t36 = load double, ptr %g.addr
+  %t37 = fmul double %t36, 0x400C000000000000
+  %t38 = fptosi double %t37 to i32
+  call i32 (ptr, ...) @printf(ptr @.write.int, i32 %t38)
+  %t39 = load i32, ptr %i.addr
+  %t40 = sitofp i32 %t39 to double
+  %t41 = load double, ptr %f.addr
+  %t42 = fcmp ogt double %t40, %t41
+  store i1 %t42, ptr %mayor.addr
+  %t43 = load i1, ptr %mayor.addr
+  %t44 = zext i1 %t43 to i32
+  call i32 (ptr, ...) @printf(ptr @.write.int, i32 %t44)
+  %t45 = load i32, ptr %i.addr
+  %t46 = mul i32 %t45, 2
+  %t47 = sitofp i32 %t46 to double
+  %t48 = fcmp oge double %t47, 0x4018000000000000
+  br i1 %t48, label %then0, label %endif0
+then0:
+  %t49 = load i32, ptr %i.addr
+  %t50 = mul i32 %t49, 2
+  %t51 = sitofp i32 %t50 to double
+  store double %t51, ptr %h.addr
+  %t52 = load double, ptr %h.addr
+  %t53 = fdiv double %t52, 0x4010000000000000
+  call i32 (ptr, ...) @printf(ptr @.write.float, double %t53)
+  br label %endif0
+endif0:
+  ret i32 0
+}
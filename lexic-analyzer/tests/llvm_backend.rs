@@ -0,0 +1,175 @@
+// Compara el IR que emite --target=llvm para los ejemplos .tny con los
+// archivos .ll de tests/llvm. Con UPDATE_GOLDEN=1 se reescriben. Si `lli`
+// esta instalado tambien ejecuta cada archivo y revisa lo que escribe.
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Output, Stdio};
+
+mod common;
+
+// Opciones con que se compila cada ejemplo y el archivo esperado. Todos se
+// compilan sin opciones a `nombre.ll`; estos ademas se compilan con opciones.
+const VARIANTS: [(&str, &str, &str); 1] = [("test_expr", "--runtime-checks", "test_expr_checks.ll")];
+
+// Archivo esperado, opciones y el ejemplo del que sale
+fn goldens() -> Vec<(String, Vec<&'static str>, common::Sample)> {
+    let mut goldens = vec![];
+    for sample in common::samples() {
+        goldens.push((format!("{}.ll", sample.name), vec![], sample));
+    }
+    for (name, flag, golden) in VARIANTS.iter() {
+        goldens.push((String::from(*golden), vec![*flag], common::sample(name)));
+    }
+    goldens
+}
+
+#[test]
+fn emitted_ir_matches_golden_files() {
+    let manifest = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    for (golden, flags, sample) in goldens() {
+        let output = Command::new(env!("CARGO_BIN_EXE_lexic-analyzer"))
+            .args(flags)
+            .arg("--target=llvm")
+            .arg(&sample.path)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{} did not compile", golden);
+        let ir = String::from_utf8(output.stdout).unwrap();
+
+        let path = manifest.join("tests/llvm").join(&golden);
+        if env::var_os("UPDATE_GOLDEN").is_some() {
+            fs::write(&path, &ir).unwrap();
+        }
+        assert_eq!(ir, fs::read_to_string(&path).unwrap(), "{}", golden);
+    }
+}
+
+// Version mayor de LLVM segun `lli --version`; None si no esta instalado
+fn lli_version() -> Option<u32> {
+    let output = Command::new("lli").arg("--version").output().ok()?;
+    let text = String::from_utf8_lossy(&output.stdout).into_owned();
+    let version = text.split("LLVM version ").nth(1)?;
+    version.split('.').next()?.trim().parse().ok()
+}
+
+// Ejecuta `file` con lli y `input` en la entrada estandar
+fn lli(version: u32, file: &Path, input: &str) -> Output {
+    let mut lli = Command::new("lli");
+    // el IR usa `ptr`, que antes de LLVM 15 necesita esta opcion
+    if version < 15 {
+        lli.arg("-opaque-pointers");
+    }
+    let mut child = lli
+        .arg(file)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn golden_files_run_with_lli() {
+    let version = match lli_version() {
+        Some(version) => version,
+        None => {
+            eprintln!("lli not found, skipping");
+            return;
+        }
+    };
+    let manifest = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    for (golden, _, sample) in goldens() {
+        let expected = match sample.output {
+            Some(output) => output,
+            None => continue,
+        };
+        let output = lli(version, &manifest.join("tests/llvm").join(&golden), &sample.input);
+        assert!(output.status.success(), "lli failed for {}:\n{}", golden, String::from_utf8_lossy(&output.stderr));
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), expected, "{}", golden);
+    }
+}
+
+// Compila el programa `source` a IR en un directorio temporal `name`
+fn compile_source(name: &str, source: &str, flags: &[&str]) -> PathBuf {
+    let dir = env::temp_dir().join(format!("tiny-llvm-{}-{}", process::id(), name));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("program.tny");
+    fs::write(&path, source).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_lexic-analyzer"))
+        .args(flags)
+        .arg("--target=llvm")
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{} did not compile", name);
+    let ir = dir.join("program.ll");
+    fs::write(&ir, &output.stdout).unwrap();
+    ir
+}
+
+#[test]
+fn division_by_zero_traps() {
+    let version = match lli_version() {
+        Some(version) => version,
+        None => {
+            eprintln!("lli not found, skipping");
+            return;
+        }
+    };
+    let ir = compile_source("division", common::DIVISION, &["--runtime-checks"]);
+    for (input, expected, trapped) in common::DIVISION_RUNS.iter() {
+        let output = lli(version, &ir, input);
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), *expected, "{}", input);
+        // una trampa termina con estado 1
+        assert_eq!(output.status.code(), Some(if *trapped { 1 } else { 0 }), "{}", input);
+    }
+    fs::remove_dir_all(ir.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn failed_reads_give_zero() {
+    let version = match lli_version() {
+        Some(version) => version,
+        None => {
+            eprintln!("lli not found, skipping");
+            return;
+        }
+    };
+    let ir = compile_source("reads", common::FAILED_READS, &[]);
+    for (input, expected) in common::FAILED_READS_RUNS.iter() {
+        let output = lli(version, &ir, input);
+        assert!(output.status.success(), "{}", input);
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), *expected, "{}", input);
+    }
+    fs::remove_dir_all(ir.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn source_file_name_is_escaped() {
+    let dir = env::temp_dir().join(format!("tiny-llvm-{}-name", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("año \"1\" \\ 2.tny");
+    fs::copy(common::sample("test_1").path, &source).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_lexic-analyzer"))
+        .arg("--target=llvm")
+        .arg(&source)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let ir = String::from_utf8(output.stdout).unwrap();
+    let name = "a\\C3\\B1o \\221\\22 \\5C 2.tny";
+    assert!(ir.starts_with(&format!("; ModuleID = '{0}'\nsource_filename = \"{0}\"\n", name)), "{}", ir);
+
+    if let Some(version) = lli_version() {
+        let ir_file = dir.join("program.ll");
+        fs::write(&ir_file, &ir).unwrap();
+        let output = lli(version, &ir_file, "");
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), "0\n0\n");
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
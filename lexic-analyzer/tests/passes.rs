@@ -0,0 +1,77 @@
+// Opciones de las pasadas de optimizacion: -O, --print-after y --time-passes.
+use std::process::Command;
+
+mod common;
+
+// Salida y errores del compilador para test_float con las opciones `args`
+fn compile(args: &[&str]) -> (String, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_lexic-analyzer"))
+        .args(args)
+        .arg(common::sample("test_float").path)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+    (String::from_utf8(output.stdout).unwrap(), String::from_utf8(output.stderr).unwrap())
+}
+
+// Pasadas que aparecen en la salida de --time-passes, en orden
+fn timed_passes(stderr: &str) -> Vec<&str> {
+    let timings = &stderr[stderr.find("pass timings:\n").expect(stderr)..];
+    timings.lines().skip(1).map(|line| line.split_whitespace().next().unwrap()).collect()
+}
+
+#[test]
+fn time_passes_lists_the_passes_of_each_level() {
+    let levels: [(&[&str], &[&str]); 5] = [
+        (&["-O0"], &["total"]),
+        (&[], &["fold", "regalloc", "peephole", "total"]),
+        (&["-O1"], &["fold", "regalloc", "peephole", "total"]),
+        (&["-O2"], &["fold", "dead-code", "regalloc", "peephole", "total"]),
+        (&["-O"], &["fold", "dead-code", "regalloc", "peephole", "total"]),
+    ];
+    for (args, expected) in levels.iter() {
+        let mut args = args.to_vec();
+        args.push("--time-passes");
+        let (_, stderr) = compile(&args);
+        assert_eq!(timed_passes(&stderr), *expected, "{:?}", args);
+    }
+
+    let (_, stderr) = compile(&["-O2", "--disable-pass=peephole", "--time-passes"]);
+    assert_eq!(timed_passes(&stderr), ["fold", "dead-code", "regalloc", "total"]);
+    let (_, stderr) = compile(&["-O0", "--enable-pass=dead_code", "--time-passes"]);
+    assert_eq!(timed_passes(&stderr), ["dead-code", "total"]);
+
+    let (_, stderr) = compile(&[]);
+    assert!(!stderr.contains("pass timings:"), "{}", stderr);
+}
+
+#[test]
+fn print_after_dumps_the_program_after_a_pass() {
+    // el codigo intermedio despues de dead-code es el que escribe --emit=ir
+    let (ir, _) = compile(&["-O2", "--emit=ir"]);
+    let (_, stderr) = compile(&["-O2", "--emit=ir", "--print-after=dead-code"]);
+    let dump = &stderr[stderr.find("*** after").expect(&stderr)..];
+    assert_eq!(dump, format!("*** after dead-code ***\n{}", ir));
+
+    let (_, stderr) = compile(&["--print-after=fold", "--print-after=regalloc"]);
+    let fold = stderr.find("*** after fold ***\n").expect(&stderr);
+    let regalloc = stderr.find("*** after regalloc ***\nt0 -> ").expect(&stderr);
+    assert!(fold < regalloc, "{}", stderr);
+
+    // una pasada que no corre no escribe nada
+    let (_, stderr) = compile(&["-O1", "--print-after=dead-code"]);
+    assert!(!stderr.contains("*** after"), "{}", stderr);
+    let (_, stderr) = compile(&["-O0", "--print-after=fold"]);
+    assert!(!stderr.contains("*** after"), "{}", stderr);
+}
+
+#[test]
+fn unknown_passes_are_rejected() {
+    let output = Command::new(env!("CARGO_BIN_EXE_lexic-analyzer"))
+        .arg("--print-after=inline")
+        .arg(common::sample("test_1").path)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr).unwrap().starts_with("error: unknown pass 'inline'\n"));
+}
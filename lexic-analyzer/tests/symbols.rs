@@ -0,0 +1,36 @@
+// Compara la salida de --emit=symbols-json y --emit=symbols-csv con los
+// archivos de tests/symbols. Con UPDATE_GOLDEN=1 se reescriben.
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+mod common;
+
+// block_locals tiene variables en bloques anidados y una que oculta a otra
+// del mismo nombre; unicode tiene nombres que no son ASCII
+const SYMBOLS: [&str; 2] = ["block_locals", "unicode"];
+
+#[test]
+fn symbol_tables_match_golden_files() {
+    let manifest = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    for name in SYMBOLS.iter() {
+        let sample = common::sample(name);
+        for format in ["json", "csv"].iter() {
+            let output = Command::new(env!("CARGO_BIN_EXE_lexic-analyzer"))
+                .arg(format!("--emit=symbols-{}", format))
+                .arg(&sample.path)
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "{} did not compile", name);
+            let golden = format!("{}.{}", name, format);
+            let table = String::from_utf8(output.stdout).unwrap();
+
+            let path = manifest.join("tests/symbols").join(&golden);
+            if env::var_os("UPDATE_GOLDEN").is_some() {
+                fs::write(&path, &table).unwrap();
+            }
+            assert_eq!(table, fs::read_to_string(&path).unwrap(), "{}", golden);
+        }
+    }
+}
@@ -0,0 +1,53 @@
+// Compila los ejemplos con --emit=tmo, desensambla el objeto con
+// --disassemble y compara las instrucciones con el codigo TM de texto.
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::{self, Command};
+
+mod common;
+
+fn run(args: &[&Path]) -> Vec<u8> {
+    let output = Command::new(env!("CARGO_BIN_EXE_lexic-analyzer")).args(args).output().unwrap();
+    assert!(output.status.success(), "{:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+    output.stdout
+}
+
+// Lineas `N: OP ...` de una salida, sin comentarios
+fn instructions(output: &[u8]) -> Vec<String> {
+    String::from_utf8_lossy(output)
+        .lines()
+        .filter(|line| {
+            let line = line.trim_start();
+            line.find(": ").is_some_and(|colon| line[..colon].chars().all(|c| c.is_ascii_digit()))
+        })
+        .map(|line| String::from(line.trim()))
+        .collect()
+}
+
+#[test]
+fn disassembly_matches_the_tm_code() {
+    for sample in common::samples() {
+        let source = &sample.path;
+        let dir = env::temp_dir().join(format!("tiny-tmo-{}-{}", process::id(), sample.name));
+        fs::create_dir_all(&dir).unwrap();
+
+        let object = dir.join("program.tmo");
+        fs::write(&object, run(&[Path::new("--emit=tmo"), source])).unwrap();
+        let disassembly = run(&[Path::new("--disassemble"), &object]);
+        let text = String::from_utf8(disassembly.clone()).unwrap();
+        assert!(text.starts_with("; data memory: "), "{}", sample.name);
+        assert!(text.contains("; line "), "{}: no source lines", sample.name);
+        let code = instructions(&run(&[source]));
+        assert!(!code.is_empty());
+        assert_eq!(instructions(&disassembly), code, "{}", sample.name);
+
+        // el texto desensamblado se vuelve a ensamblar al mismo codigo
+        let listing = dir.join("program.tm");
+        fs::write(&listing, &disassembly).unwrap();
+        let reassembled = dir.join("again.tmo");
+        fs::write(&reassembled, run(&[Path::new("--assemble"), &listing])).unwrap();
+        assert_eq!(instructions(&run(&[Path::new("--disassemble"), &reassembled])), code, "{}", sample.name);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,105 @@
+// Compila los ejemplos .tny con --target=wat, valida el modulo con wat y lo
+// ejecuta con wasmi, comparando lo que escribe con la salida esperada.
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::{self, Command};
+use wasmi::{Caller, Engine, Linker, Module, Store};
+
+mod common;
+
+struct Host {
+    input: Vec<String>,
+    output: String,
+}
+
+impl Host {
+    fn next(&mut self) -> String {
+        if self.input.is_empty() {
+            String::new()
+        } else {
+            self.input.remove(0)
+        }
+    }
+}
+
+fn compile_and_run(sample: &Path, input: &str, flags: &[&str]) -> (String, bool) {
+    let output = Command::new(env!("CARGO_BIN_EXE_lexic-analyzer"))
+        .args(flags)
+        .arg("--target=wat")
+        .arg(sample)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{} did not compile", sample.display());
+
+    let wasm = wat::parse_str(String::from_utf8(output.stdout).unwrap()).unwrap();
+    let engine = Engine::default();
+    let module = Module::new(&engine, &wasm[..]).unwrap();
+    let host = Host {
+        input: input.split_whitespace().map(String::from).collect(),
+        output: String::new(),
+    };
+    let mut store = Store::new(&engine, host);
+    let mut linker = <Linker<Host>>::new(&engine);
+    linker
+        .func_wrap("env", "read_i32", |mut caller: Caller<'_, Host>| -> i32 {
+            caller.data_mut().next().parse().unwrap_or(0)
+        })
+        .unwrap();
+    linker
+        .func_wrap("env", "read_f64", |mut caller: Caller<'_, Host>| -> f64 {
+            caller.data_mut().next().parse().unwrap_or(0.0)
+        })
+        .unwrap();
+    linker
+        .func_wrap("env", "write_i32", |mut caller: Caller<'_, Host>, value: i32| {
+            caller.data_mut().output.push_str(&format!("{}\n", value));
+        })
+        .unwrap();
+    linker
+        .func_wrap("env", "write_f64", |mut caller: Caller<'_, Host>, value: f64| {
+            caller.data_mut().output.push_str(&format!("{}\n", common::format_g(value)));
+        })
+        .unwrap();
+    let instance = linker.instantiate(&mut store, &module).unwrap().start(&mut store).unwrap();
+    let main = instance.get_typed_func::<(), ()>(&store, "main").unwrap();
+    // una revision que falla termina con `unreachable`
+    let trapped = main.call(&mut store, ()).is_err();
+    (store.into_data().output, trapped)
+}
+
+#[test]
+fn samples_match_expected_output() {
+    for (sample, expected) in common::terminating() {
+        assert_eq!(compile_and_run(&sample.path, &sample.input, &[]), (expected, false), "{}", sample.name);
+    }
+}
+
+#[test]
+fn runtime_checks_do_not_change_output() {
+    for (sample, expected) in common::terminating() {
+        let result = compile_and_run(&sample.path, &sample.input, &["--runtime-checks"]);
+        assert_eq!(result, (expected, false), "{}", sample.name);
+    }
+}
+
+#[test]
+fn division_by_zero_traps() {
+    let path = env::temp_dir().join(format!("tiny-wat-{}-division.tny", process::id()));
+    fs::write(&path, common::DIVISION).unwrap();
+    for (input, expected, trapped) in common::DIVISION_RUNS.iter() {
+        let result = compile_and_run(&path, input, &["--runtime-checks"]);
+        assert_eq!(result, (String::from(*expected), *trapped), "{}", input);
+    }
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn failed_reads_give_zero() {
+    let path = env::temp_dir().join(format!("tiny-wat-{}-reads.tny", process::id()));
+    fs::write(&path, common::FAILED_READS).unwrap();
+    for (input, expected) in common::FAILED_READS_RUNS.iter() {
+        assert_eq!(compile_and_run(&path, input, &[]), (String::from(*expected), false), "{}", input);
+    }
+    fs::remove_file(&path).unwrap();
+}